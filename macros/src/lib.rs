@@ -0,0 +1,118 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Index, LitStr, Member};
+
+/// Implements `bhecs::resource::Resource`, so `&T` and `&mut T` can be system params.
+#[proc_macro_derive(Resource)]
+pub fn derive_resource(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::bhecs::resource::Resource for #name #ty_generics #where_clause {}
+    }
+    .into()
+}
+
+/// Implements `bhecs::typeregistry::Registered`. The optional `#[registered(...)]` attribute takes
+/// `name = "..."`, the name the type is registered under, which defaults to the type's own name,
+/// and the capabilities to register, which are the methods of `bhecs::typeregistry::RegisterType`:
+///
+/// ```ignore
+/// #[derive(Clone, Debug, Registered)]
+/// #[registered(name = "Health", with_clone, with_debug)]
+/// struct Health(u32);
+/// ```
+#[proc_macro_derive(Registered, attributes(registered))]
+pub fn derive_registered(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let name = &ast.ident;
+    let mut registered_name = name.to_string();
+    let mut capabilities = Vec::new();
+    for attr in ast.attrs.iter().filter(|attr| attr.path().is_ident("registered")) {
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                registered_name = meta.value()?.parse::<LitStr>()?.value();
+            } else if let Some(capability) = meta.path.get_ident() {
+                capabilities.push(capability.clone());
+            } else {
+                return Err(meta.error("expected `name = \"...\"` or a capability"));
+            }
+            Ok(())
+        });
+        if let Err(error) = parsed {
+            return error.to_compile_error().into();
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::bhecs::typeregistry::Registered for #name #ty_generics #where_clause {
+            fn register(registry: &mut ::bhecs::typeregistry::TypeRegistry) {
+                let _ = registry.register_named::<Self>(#registered_name)#(.#capabilities())*;
+            }
+        }
+    }
+    .into()
+}
+
+/// Implements `bhecs::reflect::Reflect` for a struct, exposing its fields, which must implement it too.
+/// Tuple struct fields are named `0`, `1` and so on. Leave a field out with `#[reflect(ignore)]`.
+#[proc_macro_derive(Reflect, attributes(reflect))]
+pub fn derive_reflect(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let name = &ast.ident;
+    let Data::Struct(data) = &ast.data else {
+        return syn::Error::new_spanned(name, "Reflect can only be derived for structs").to_compile_error().into();
+    };
+    let mut names = Vec::new();
+    let mut members = Vec::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        let mut ignore = false;
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("reflect")) {
+            let parsed = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("ignore") {
+                    ignore = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `ignore`"))
+                }
+            });
+            if let Err(error) = parsed {
+                return error.to_compile_error().into();
+            }
+        }
+        if ignore {
+            continue;
+        }
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        };
+        names.push(match &member {
+            Member::Named(ident) => ident.to_string(),
+            Member::Unnamed(index) => index.index.to_string(),
+        });
+        members.push(member);
+    }
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::bhecs::reflect::Reflect for #name #ty_generics #where_clause {
+            fn fields(&self) -> &'static [&'static str] {
+                &[#(#names),*]
+            }
+            fn field(&self, name: &str) -> ::core::option::Option<&dyn ::bhecs::reflect::Reflect> {
+                match name {
+                    #(#names => ::core::option::Option::Some(&self.#members),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+            fn field_mut(&mut self, name: &str) -> ::core::option::Option<&mut dyn ::bhecs::reflect::Reflect> {
+                match name {
+                    #(#names => ::core::option::Option::Some(&mut self.#members),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    }
+    .into()
+}
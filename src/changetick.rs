@@ -0,0 +1,107 @@
+//! Change ticks are `u32`s that wrap around. Ticks are only ever compared relative to the current tick,
+//! and [`World::check_change_ticks`](crate::World::check_change_ticks) keeps stored ticks from
+//! falling so far behind that the comparison wraps.
+//!
+//! Every run of a system, normal or exclusive, first advances the world's tick. That tick is the
+//! run's *this run* tick: params get it as `change_tick`, and writes made during the run belong to it.
+//! The system's [`SystemMeta::last_run`](crate::system::systemmeta::SystemMeta) holds the *this run* tick
+//! of its previous run, so "changed since last run" means `is_newer_than(tick, last_run, this_run)`.
+//! Systems read both through [`SystemChangeTick`](crate::system::systemchangetick::SystemChangeTick).
+use crate::ChangeTick;
+
+/// How many ticks may pass between two effective [`World::check_change_ticks`](crate::World::check_change_ticks) passes.
+pub const CHECK_TICK_THRESHOLD: ChangeTick = 518_400_000;
+
+/// Stored ticks are clamped to be at most this old.
+/// Leaves room for [`CHECK_TICK_THRESHOLD`] more ticks before any comparison can wrap.
+pub const MAX_CHANGE_AGE: ChangeTick = ChangeTick::MAX - (2 * CHECK_TICK_THRESHOLD - 1);
+
+/// Returns true if `tick` happened after `last_run`, both seen from `this_run`.
+#[must_use]
+pub fn is_newer_than(tick: ChangeTick, last_run: ChangeTick, this_run: ChangeTick) -> bool {
+    let since_tick = this_run.wrapping_sub(tick).min(MAX_CHANGE_AGE);
+    let since_last_run = this_run.wrapping_sub(last_run).min(MAX_CHANGE_AGE);
+    since_last_run > since_tick
+}
+
+/// Clamps `tick` to be at most [`MAX_CHANGE_AGE`] older than `change_tick`. Returns true if it was clamped.
+pub fn check_tick(tick: &mut ChangeTick, change_tick: ChangeTick) -> bool {
+    if change_tick.wrapping_sub(*tick) > MAX_CHANGE_AGE {
+        *tick = change_tick.wrapping_sub(MAX_CHANGE_AGE);
+        return true;
+    }
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{event::Event, observer::Trigger, resource::Resource, system::{systemchangetick::SystemChangeTick, IntoSystem, System}, World};
+
+    use super::*;
+
+    #[test]
+    fn newer_across_wrap() {
+        let last_run = ChangeTick::MAX - 5;
+        let this_run = 10;
+        assert!(is_newer_than(2, last_run, this_run));
+        assert!(!is_newer_than(ChangeTick::MAX - 7, last_run, this_run));
+    }
+
+    #[test]
+    fn clamp_keeps_order() {
+        let mut tick = 3;
+        let change_tick = 3 + MAX_CHANGE_AGE + 100;
+        assert!(check_tick(&mut tick, change_tick));
+        assert_eq!(MAX_CHANGE_AGE, change_tick.wrapping_sub(tick));
+        assert!(!check_tick(&mut tick, change_tick));
+    }
+
+    fn noop() {}
+
+    #[test]
+    fn billions_of_ticks() {
+        let mut w = World::new();
+        let mut s = IntoSystem::into_system(noop);
+        s.run((), &mut w);
+        let stale = s.system_meta.last_run;
+        // ~10 billion ticks, checked as often as the threshold requires.
+        for _ in 0..20 {
+            w.change_tick = w.change_tick.wrapping_add(CHECK_TICK_THRESHOLD);
+            w.check_change_ticks();
+            s.check_change_tick(w.change_tick());
+            s.run((), &mut w);
+            assert_eq!(w.change_tick(), s.system_meta.last_run);
+        }
+        // a system which was not run for a long time is clamped, not wrapped into looking recent.
+        let mut idle = IntoSystem::into_system(noop);
+        idle.run((), &mut w);
+        for _ in 0..20 {
+            w.change_tick = w.change_tick.wrapping_add(CHECK_TICK_THRESHOLD);
+            idle.check_change_tick(w.change_tick());
+            assert!(w.change_tick().wrapping_sub(idle.system_meta.last_run) <= MAX_CHANGE_AGE);
+        }
+        assert!(!is_newer_than(stale, idle.system_meta.last_run, w.change_tick()));
+    }
+
+    struct Ping;
+    impl Event for Ping {}
+    #[derive(Resource)]
+    struct Age(ChangeTick);
+
+    #[test]
+    fn observer_ticks_are_checked() {
+        let mut w = World::new();
+        w.insert_resource(Age(0));
+        w.observe(|_: Trigger<Ping>, ticks: SystemChangeTick, age: &mut Age| {
+            age.0 = ticks.this_run().wrapping_sub(ticks.last_run());
+        });
+        w.trigger(Ping);
+        w.change_tick = w.change_tick.wrapping_add(3_500_000_000);
+        // any system run checks the world's ticks, the observer's included.
+        w.run_system_once(noop);
+        // far enough to wrap around, had the observer not been clamped above.
+        w.change_tick = w.change_tick.wrapping_add(1_000_000_000);
+        w.trigger(Ping);
+        assert!(w.get_resource::<Age>().0 > CHECK_TICK_THRESHOLD);
+    }
+}
@@ -0,0 +1,226 @@
+//! Duplicating entities, through the components registered with
+//! [`RegisterType::with_clone`](crate::typeregistry::RegisterType::with_clone).
+use core::any::TypeId;
+
+use hecs::{Entity, EntityBuilderClone, NoSuchEntity};
+use bevy_utils::tracing::warn;
+
+use crate::{
+    entitymap::EntityMap,
+    hierarchy::{Children, Parent},
+    system::commands::EntityCommands,
+    typeregistry::TypeRegistry,
+    World,
+};
+
+impl World {
+    /// Spawns a copy of `entity`'s Clone-registered components, running hooks and observers.
+    /// A child is copied as a sibling, without the children. [`RelationshipTarget`](crate::relationship::RelationshipTarget)s
+    /// aren't copied, since the sources still point at `entity`; a copied relationship is added to its target's list.
+    pub fn clone_entity(&mut self, entity: Entity) -> Result<Entity, NoSuchEntity> {
+        if !self.hworld.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        let clone = self.hworld.reserve_entity();
+        self.clone_into(entity, clone, false);
+        Ok(clone)
+    }
+    /// Like [`clone_entity`](Self::clone_entity), but copies the descendants too. References between
+    /// the copied entities, in registered [`with_map_entities`](crate::typeregistry::RegisterType::with_map_entities)
+    /// components, are remapped to the copies.
+    pub fn clone_entity_recursive(&mut self, entity: Entity) -> Result<Entity, NoSuchEntity> {
+        if !self.hworld.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        let clone = self.hworld.reserve_entity();
+        self.clone_into(entity, clone, true);
+        Ok(clone)
+    }
+
+    /// Clones the existing `source` into the reserved `target`.
+    fn clone_into(&mut self, source: Entity, target: Entity, recursive: bool) {
+        let sources = if recursive { self.subtree(source) } else { vec![source] };
+        // (parent, child) pairs among the sources, in order.
+        let mut links = Vec::new();
+        if recursive {
+            for &parent in &sources {
+                if let Ok(children) = self.hworld.get::<&Children>(parent) {
+                    links.extend(children.iter().map(|&child| (parent, child)));
+                }
+            }
+        }
+        // copied into a staging world first, so references can be remapped before hooks see them.
+        let mut staging = hecs::World::new();
+        let hierarchy = [TypeId::of::<Parent>(), TypeId::of::<Children>()];
+        let registry = self.hworld.get::<&TypeRegistry>(self.resource_entity()).ok();
+        let mut staged = Vec::with_capacity(sources.len());
+        for &source in &sources {
+            let entity = self.hworld.entity(source).expect("sources exist");
+            let mut builder = EntityBuilderClone::new();
+            let copied = |type_id: &TypeId| !hierarchy.contains(type_id) && !self.is_relationship_target(*type_id);
+            for type_id in entity.component_types().filter(copied) {
+                if let Some(clone) = registry.as_ref().and_then(|registry| registry.get(type_id)?.clone) {
+                    clone(&entity, &mut builder);
+                }
+            }
+            staged.push(staging.spawn(&builder.build()));
+        }
+        let map_fns: Vec<_> = registry.iter().flat_map(|registry| registry.iter())
+            .filter_map(|registration| registration.map_entities).collect();
+        drop(registry);
+
+        let mut map = EntityMap::default();
+        map.insert(source, target);
+        for &source in &sources[1..] {
+            map.insert(source, self.hworld.reserve_entity());
+        }
+        for &entity in &staged {
+            for map_entities in &map_fns {
+                map_entities(&staging, entity, &map);
+            }
+        }
+        for (&source, &staged) in sources.iter().zip(&staged) {
+            let components = staging.take(staged).expect("staged entity");
+            self.spawn_at(map.map(source), components);
+        }
+        if let Ok(parent) = self.hworld.get::<&Parent>(source).map(|parent| parent.get()) {
+            let _ = self.set_parent(target, parent);
+        }
+        for (parent, child) in links {
+            let _ = self.set_parent(map.map(child), map.map(parent));
+        }
+    }
+}
+
+impl EntityCommands<'_> {
+    /// Queues [`World::clone_entity`], returning the commands of the copy.
+    pub fn clone_and_spawn(&mut self) -> EntityCommands<'_> {
+        self.clone_and_spawn_with(false)
+    }
+    /// Queues [`World::clone_entity_recursive`], returning the commands of the copy.
+    pub fn clone_and_spawn_recursive(&mut self) -> EntityCommands<'_> {
+        self.clone_and_spawn_with(true)
+    }
+    fn clone_and_spawn_with(&mut self, recursive: bool) -> EntityCommands<'_> {
+        let source = self.id();
+        let mut clone = self.reserve();
+        clone.add(move |clone, world| {
+            if world.contains(source) {
+                world.clone_into(source, clone, recursive);
+            } else {
+                warn!("could not clone {source:?}, it does not exist");
+                let _ = world.hworld.despawn(clone);
+            }
+        });
+        clone
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{entitymap::MapEntities, relationship::{Relationship, RelationshipTarget}, system::{commands::Commands, IntoSystem, System}};
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Health(u32);
+    /// Not registered, so not cloned.
+    struct Unique;
+    #[derive(Clone, Debug, PartialEq)]
+    struct Target(Entity);
+    impl MapEntities for Target {
+        fn map_entities(&mut self, map: &EntityMap) { self.0 = map.map(self.0); }
+    }
+
+    #[test]
+    fn shallow() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Health>().with_clone();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        let parent = w.spawn(());
+        let e = w.spawn((Health(3), Unique));
+        let child = w.spawn(());
+        w.add_child(parent, e).unwrap();
+        w.add_child(e, child).unwrap();
+        let copy = w.clone_entity(e).unwrap();
+        assert_eq!(Health(3), *w.get::<&Health>(copy).unwrap());
+        assert!(w.get::<&Unique>(copy).is_err());
+        assert_eq!(parent, w.get::<&Parent>(copy).unwrap().get());
+        assert_eq!([e, copy], w.get::<&Children>(parent).unwrap()[..]);
+        assert!(w.get::<&Children>(copy).is_err());
+    }
+
+    #[derive(Clone)]
+    struct Likes(Entity);
+    #[derive(Clone)]
+    struct LikedBy(Vec<Entity>);
+    impl Relationship for Likes {
+        type Target = LikedBy;
+
+        fn target(&self) -> Entity { self.0 }
+        fn set_target(&mut self, target: Entity) { self.0 = target; }
+    }
+    impl RelationshipTarget for LikedBy {
+        fn from_sources(sources: Vec<Entity>) -> Self { Self(sources) }
+        fn sources(&self) -> &[Entity] { &self.0 }
+        fn sources_mut(&mut self) -> &mut Vec<Entity> { &mut self.0 }
+    }
+
+    #[test]
+    fn relationships() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Likes>().with_clone();
+        let _ = registry.register::<LikedBy>().with_clone();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        w.register_relationship::<Likes>();
+        let liked = w.spawn(());
+        let fan = w.spawn((Likes(liked),));
+        // the fan still likes the original only.
+        let liked_copy = w.clone_entity(liked).unwrap();
+        assert!(w.get::<&LikedBy>(liked_copy).is_err());
+        let fan_copy = w.clone_entity(fan).unwrap();
+        assert_eq!(vec![fan, fan_copy], w.related::<Likes>(liked));
+    }
+
+    #[test]
+    fn recursive_remaps() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Health>().with_clone();
+        let _ = registry.register::<Target>().with_clone().with_map_entities();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        let outside = w.spawn(());
+        let root = w.spawn((Health(10),));
+        let child = w.spawn((Target(root),));
+        let grandchild = w.spawn((Target(outside),));
+        w.add_child(root, child).unwrap();
+        w.add_child(child, grandchild).unwrap();
+
+        let copy = w.clone_entity_recursive(root).unwrap();
+        let child_copy = w.get::<&Children>(copy).unwrap()[0];
+        let grandchild_copy = w.get::<&Children>(child_copy).unwrap()[0];
+        assert_ne!(child, child_copy);
+        assert_eq!(Target(copy), *w.get::<&Target>(child_copy).unwrap());
+        assert_eq!(Target(outside), *w.get::<&Target>(grandchild_copy).unwrap());
+        assert_eq!(Target(root), *w.get::<&Target>(child).unwrap());
+    }
+
+    #[test]
+    fn commands() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Health>().with_clone();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        let template = w.spawn((Health(5),));
+        let mut s = IntoSystem::into_system(move |mut commands: Commands| {
+            let mut entity = commands.entity(template);
+            entity.clone_and_spawn().insert((Unique,)).id()
+        });
+        let copy = s.run((), &mut w);
+        assert_eq!(Health(5), *w.get::<&Health>(copy).unwrap());
+        assert!(w.get::<&Unique>(copy).is_ok());
+        assert!(w.get::<&Unique>(template).is_err());
+    }
+}
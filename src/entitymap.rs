@@ -0,0 +1,56 @@
+use bevy_utils::HashMap;
+use hecs::Entity;
+
+/// Maps entity ids of one world, or of saved data, to entity ids of another world.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EntityMap(HashMap<Entity, Entity>);
+
+impl EntityMap {
+    pub fn insert(&mut self, from: Entity, to: Entity) {
+        self.0.insert(from, to);
+    }
+    /// The entity `from` maps to, if any.
+    #[must_use]
+    pub fn get(&self, from: Entity) -> Option<Entity> {
+        self.0.get(&from).copied()
+    }
+    /// The entity `from` maps to, or `from` itself if it isn't mapped,
+    /// e.g. a reference to an entity outside the copied set.
+    #[must_use]
+    pub fn map(&self, from: Entity) -> Entity {
+        self.get(from).unwrap_or(from)
+    }
+    #[must_use]
+    pub fn len(&self) -> usize { self.0.len() }
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.0.iter().map(|(&from, &to)| (from, to))
+    }
+}
+
+/// Components which refer to other entities, and need those references
+/// updated when they are copied to another world. See [`EntityMap`].
+pub trait MapEntities {
+    fn map_entities(&mut self, map: &EntityMap);
+}
+
+impl MapEntities for Entity {
+    fn map_entities(&mut self, map: &EntityMap) {
+        *self = map.map(*self);
+    }
+}
+impl<T: MapEntities> MapEntities for Vec<T> {
+    fn map_entities(&mut self, map: &EntityMap) {
+        for item in self {
+            item.map_entities(map);
+        }
+    }
+}
+impl<T: MapEntities> MapEntities for Option<T> {
+    fn map_entities(&mut self, map: &EntityMap) {
+        if let Some(item) = self {
+            item.map_entities(map);
+        }
+    }
+}
@@ -1,8 +1,13 @@
 use hecs::Component;
 
-use crate::system::systeminput::SystemInput;
+use crate::{system::systeminput::SystemInput, World};
 
-pub trait Event: Component {}
+pub trait Event: Component {
+    /// Called whenever an observer of this event is added, see [`World::observe`].
+    /// Lets built-in events like [`OnAdd`](crate::observer::OnAdd) hook into the world.
+    #[allow(unused_variables)]
+    fn on_observe(world: &mut World) {}
+}
 // impl<E: Component> Event for E {}
 
 // impl<E: Event> SystemInput for E {
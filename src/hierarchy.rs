@@ -0,0 +1,243 @@
+//! Parent/child links between entities. [`Parent`] is a [`Relationship`] registered by every [`World`],
+//! so [`Children`] is kept up to date by its hooks, and despawning a parent detaches its children.
+//! Use the [`World`] and [`EntityCommands`] methods here to also reject cycles.
+pub mod traversal;
+
+use bevy_utils::tracing::warn;
+use derive_more::derive::Deref;
+use hecs::{Entity, NoSuchEntity};
+
+use crate::{
+    entitymap::{EntityMap, MapEntities},
+    relationship::{Relationship, RelationshipTarget},
+    system::commands::EntityCommands,
+    World,
+};
+
+/// The parent of an entity.
+#[derive(Deref, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parent(Entity);
+
+impl Parent {
+    #[must_use]
+    pub fn get(&self) -> Entity { self.0 }
+}
+
+/// The children of an entity, in the order they were added. Never empty, the component is removed instead.
+#[derive(Deref, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Children(Vec<Entity>);
+
+impl MapEntities for Parent {
+    fn map_entities(&mut self, map: &EntityMap) { self.0.map_entities(map); }
+}
+impl MapEntities for Children {
+    fn map_entities(&mut self, map: &EntityMap) { self.0.map_entities(map); }
+}
+
+/// Error from [`World::set_parent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyError {
+    /// The child or the parent doesn't exist.
+    NoSuchEntity,
+    /// The parent is the child itself, or one of its descendants.
+    Cycle,
+}
+impl From<NoSuchEntity> for HierarchyError {
+    fn from(_: NoSuchEntity) -> Self { Self::NoSuchEntity }
+}
+
+impl Relationship for Parent {
+    type Target = Children;
+
+    fn target(&self) -> Entity { self.0 }
+    fn set_target(&mut self, target: Entity) { self.0 = target; }
+}
+impl RelationshipTarget for Children {
+    fn from_sources(sources: Vec<Entity>) -> Self { Self(sources) }
+    fn sources(&self) -> &[Entity] { &self.0 }
+    fn sources_mut(&mut self) -> &mut Vec<Entity> { &mut self.0 }
+}
+
+impl World {
+    /// Makes `child` a child of `parent`, detaching it from its old parent first.
+    ///
+    /// # Errors
+    /// If either entity doesn't exist, or `parent` is `child` or one of its descendants.
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<(), HierarchyError> {
+        if !self.hworld.contains(child) || !self.hworld.contains(parent) {
+            return Err(HierarchyError::NoSuchEntity);
+        }
+        if self.hworld.get::<&Parent>(child).is_ok_and(|p| p.0 == parent) {
+            return Ok(());
+        }
+        let mut ancestor = Some(parent);
+        while let Some(entity) = ancestor {
+            if entity == child {
+                return Err(HierarchyError::Cycle);
+            }
+            ancestor = self.hworld.get::<&Parent>(entity).ok().map(|p| p.0);
+        }
+        // the relationship hooks move the child from the children of the old parent to the new ones.
+        self.insert_one(child, Parent(parent))?;
+        Ok(())
+    }
+    /// Same as [`set_parent`](Self::set_parent).
+    pub fn add_child(&mut self, parent: Entity, child: Entity) -> Result<(), HierarchyError> {
+        self.set_parent(child, parent)
+    }
+    /// Detaches `child` from `parent`. Does nothing if it isn't a child of `parent`.
+    pub fn remove_child(&mut self, parent: Entity, child: Entity) {
+        if self.hworld.get::<&Parent>(child).is_ok_and(|p| p.0 == parent) {
+            let _ = self.remove_one::<Parent>(child);
+        }
+    }
+    /// Detaches `child` from whatever parent it has.
+    pub fn remove_parent(&mut self, child: Entity) {
+        if let Ok(parent) = self.hworld.get::<&Parent>(child).map(|p| p.0) {
+            self.remove_child(parent, child);
+        }
+    }
+    /// Despawns `entity` and all its descendants, and detaches it from its parent.
+    pub fn despawn_recursive(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        if !self.hworld.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        self.remove_parent(entity);
+        let mut stack = vec![entity];
+        while let Some(e) = stack.pop() {
+            if let Ok(children) = self.hworld.get::<&Children>(e) {
+                stack.extend_from_slice(&children.0);
+            }
+            let _ = self.despawn(e);
+        }
+        Ok(())
+    }
+    /// `root` and its descendants, every parent before its children.
+    pub(crate) fn subtree(&self, root: Entity) -> Vec<Entity> {
+        let mut entities = vec![root];
+        let mut next = 0;
+        while let Some(&e) = entities.get(next) {
+            if let Ok(children) = self.hworld.get::<&Children>(e) {
+                entities.extend_from_slice(&children.0);
+            }
+            next += 1;
+        }
+        entities
+    }
+}
+
+impl EntityCommands<'_> {
+    pub fn set_parent(&mut self, parent: Entity) -> &mut Self {
+        self.add(move |child, world| {
+            if let Err(error) = world.set_parent(child, parent) {
+                warn!("could not make {parent:?} the parent of {child:?}: {error:?}");
+            }
+        })
+    }
+    pub fn add_child(&mut self, child: Entity) -> &mut Self {
+        self.add(move |parent, world| {
+            if let Err(error) = world.add_child(parent, child) {
+                warn!("could not make {parent:?} the parent of {child:?}: {error:?}");
+            }
+        })
+    }
+    pub fn remove_child(&mut self, child: Entity) -> &mut Self {
+        self.add(move |parent, world| world.remove_child(parent, child))
+    }
+    pub fn remove_parent(&mut self) -> &mut Self {
+        self.add(|child, world| world.remove_parent(child))
+    }
+    pub fn despawn_recursive(&mut self) {
+        self.add(|entity, world| {
+            let _ = world.despawn_recursive(entity);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::system::{commands::Commands, IntoSystem, System};
+
+    use super::*;
+
+    fn children(w: &World, e: Entity) -> Vec<Entity> {
+        w.get::<&Children>(e).map(|c| c.to_vec()).unwrap_or_default()
+    }
+
+    #[test]
+    fn reparent() {
+        let mut w = World::new();
+        let [a, b, c] = [(), (), ()].map(|()| w.spawn(()));
+        w.add_child(a, b).unwrap();
+        w.add_child(a, c).unwrap();
+        assert_eq!(vec![b, c], children(&w, a));
+        w.set_parent(b, c).unwrap();
+        assert_eq!(vec![c], children(&w, a));
+        assert_eq!(c, w.get::<&Parent>(b).unwrap().get());
+        w.remove_child(a, b);
+        assert_eq!(c, w.get::<&Parent>(b).unwrap().get());
+        w.remove_child(a, c);
+        assert!(w.get::<&Parent>(c).is_err());
+        assert!(w.get::<&Children>(a).is_err());
+    }
+
+    #[test]
+    fn cycles() {
+        let mut w = World::new();
+        let [a, b, c] = [(), (), ()].map(|()| w.spawn(()));
+        assert_eq!(Err(HierarchyError::Cycle), w.set_parent(a, a));
+        w.add_child(a, b).unwrap();
+        w.add_child(b, c).unwrap();
+        assert_eq!(Err(HierarchyError::Cycle), w.set_parent(a, c));
+        assert!(w.get::<&Parent>(a).is_err());
+        w.despawn(c).unwrap();
+        assert_eq!(Err(HierarchyError::NoSuchEntity), w.set_parent(c, a));
+    }
+
+    #[test]
+    fn plain_despawn() {
+        let mut w = World::new();
+        let [root, a, b] = [(), (), ()].map(|()| w.spawn(()));
+        w.add_child(root, a).unwrap();
+        w.add_child(root, b).unwrap();
+        w.despawn(a).unwrap();
+        assert_eq!(vec![b], children(&w, root));
+        w.despawn(root).unwrap();
+        assert!(w.get::<&Parent>(b).is_err());
+    }
+
+    #[test]
+    fn despawn_subtree() {
+        let mut w = World::new();
+        let [root, a, b, aa, other] = [(), (), (), (), ()].map(|()| w.spawn(()));
+        w.add_child(root, a).unwrap();
+        w.add_child(root, b).unwrap();
+        w.add_child(a, aa).unwrap();
+        w.add_child(b, other).unwrap();
+        w.remove_child(b, other);
+        w.despawn_recursive(a).unwrap();
+        assert!(!w.contains(a) && !w.contains(aa));
+        assert_eq!(vec![b], children(&w, root));
+        w.despawn_recursive(root).unwrap();
+        assert!(!w.contains(root) && !w.contains(b));
+        assert!(w.contains(other));
+    }
+
+    #[test]
+    fn commands() {
+        let mut w = World::new();
+        let root = w.spawn(());
+        let mut s = IntoSystem::into_system(move |mut commands: Commands| {
+            let child = commands.spawn(()).set_parent(root).id();
+            commands.spawn(()).set_parent(child);
+        });
+        s.run((), &mut w);
+        let child = children(&w, root)[0];
+        assert_eq!(1, children(&w, child).len());
+        let mut despawn = IntoSystem::into_system(move |mut commands: Commands| commands.entity(root).despawn_recursive());
+        despawn.run((), &mut w);
+        assert_eq!(1, w.iter().count(), "only the resource entity is left");
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::VecDeque;
+
+use hecs::{Component, Entity};
+
+use super::{Children, Parent};
+use crate::{system::query::filter::QueryFilter, Query};
+
+/// A query over the hierarchy links, which gives the [`HierarchyQueryExt`] traversals.
+pub type HierarchyQuery<'w, F = ()> = Query<'w, (Option<&'static Parent>, Option<&'static Children>), F>;
+
+/// Finds the parent and children of an entity.
+pub trait HierarchyLookup {
+    fn parent(&self, entity: Entity) -> Option<Entity>;
+    /// Empty if the entity has no children.
+    fn children(&self, entity: Entity) -> &[Entity];
+}
+
+impl<F: QueryFilter> HierarchyLookup for HierarchyQuery<'_, F> {
+    fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.get(entity).and_then(|(parent, _)| parent).map(Parent::get)
+    }
+    fn children(&self, entity: Entity) -> &[Entity] {
+        self.get(entity).and_then(|(_, children)| children).map_or(&[], |children| &children.0)
+    }
+}
+
+/// Traversals of the hierarchy, for anything which can look up parents and children.
+pub trait HierarchyQueryExt: HierarchyLookup + Sized {
+    /// The parent of `entity`, its parent, and so on up to the root.
+    fn iter_ancestors(&self, entity: Entity) -> AncestorIter<'_, Self> {
+        AncestorIter { lookup: self, next: self.parent(entity) }
+    }
+    /// The topmost ancestor of `entity`, or `entity` itself if it has no parent.
+    fn root_ancestor(&self, entity: Entity) -> Entity {
+        self.iter_ancestors(entity).last().unwrap_or(entity)
+    }
+    /// All descendants of `entity`, depth-first. Each parent comes before its children.
+    fn iter_descendants(&self, entity: Entity) -> DescendantIter<'_, Self> {
+        let mut stack = self.children(entity).to_vec();
+        stack.reverse();
+        DescendantIter { lookup: self, stack }
+    }
+    /// All descendants of `entity`, breadth-first. Children come before grandchildren.
+    fn iter_descendants_breadth_first(&self, entity: Entity) -> DescendantBreadthFirstIter<'_, Self> {
+        DescendantBreadthFirstIter { lookup: self, queue: self.children(entity).iter().copied().collect() }
+    }
+}
+impl<L: HierarchyLookup> HierarchyQueryExt for L {}
+
+pub struct AncestorIter<'a, L: HierarchyLookup> {
+    lookup: &'a L,
+    next: Option<Entity>,
+}
+impl<L: HierarchyLookup> Iterator for AncestorIter<'_, L> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entity = self.next?;
+        self.next = self.lookup.parent(entity);
+        Some(entity)
+    }
+}
+
+pub struct DescendantIter<'a, L: HierarchyLookup> {
+    lookup: &'a L,
+    stack: Vec<Entity>,
+}
+impl<L: HierarchyLookup> Iterator for DescendantIter<'_, L> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entity = self.stack.pop()?;
+        self.stack.extend(self.lookup.children(entity).iter().rev());
+        Some(entity)
+    }
+}
+
+pub struct DescendantBreadthFirstIter<'a, L: HierarchyLookup> {
+    lookup: &'a L,
+    queue: VecDeque<Entity>,
+}
+impl<L: HierarchyLookup> Iterator for DescendantBreadthFirstIter<'_, L> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entity = self.queue.pop_front()?;
+        self.queue.extend(self.lookup.children(entity));
+        Some(entity)
+    }
+}
+
+/// The query [`propagate`] works on: a local value `L` and the propagated value `G`.
+pub type PropagateQuery<'w, L, G> = Query<'w, (&'static L, &'static mut G, Option<&'static Parent>, Option<&'static Children>)>;
+
+/// Sets `G` of every entity in `query` to `combine(parent's G, own L)`, parents first.
+/// Entities whose parent isn't in `query` get `combine(None, own L)`.
+/// Used for things like global transforms or inherited visibility.
+pub fn propagate<L: Component, G: Component + Clone>(query: &mut PropagateQuery<L, G>, combine: impl Fn(Option<&G>, &L) -> G) {
+    let roots: Vec<Entity> = query.iter_mut()
+        .filter(|(_, (_, _, parent, _))| parent.is_none())
+        .map(|(entity, _)| entity)
+        .collect();
+    let roots: Vec<(Entity, Option<G>)> = roots.into_iter()
+        .chain(orphans(query))
+        .map(|entity| (entity, None))
+        .collect();
+    let mut stack = roots;
+    while let Some((entity, inherited)) = stack.pop() {
+        let Some((local, global, _, children)) = query.get_mut(entity) else {
+            continue;
+        };
+        *global = combine(inherited.as_ref(), local);
+        let value = global.clone();
+        if let Some(children) = children {
+            stack.extend(children.iter().map(|&child| (child, Some(value.clone()))));
+        }
+    }
+}
+/// Entities with a parent which isn't in the query.
+fn orphans<L: Component, G: Component>(query: &mut PropagateQuery<L, G>) -> Vec<Entity> {
+    let with_parent: Vec<(Entity, Entity)> = query.iter_mut()
+        .filter_map(|(entity, (_, _, parent, _))| parent.map(|p| (entity, p.get())))
+        .collect();
+    with_parent.into_iter().filter(|&(_, parent)| !query.contains(parent)).map(|(entity, _)| entity).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{system::{IntoSystem, System}, World};
+
+    use super::*;
+
+    /// root -> (a -> (aa, ab), b)
+    fn tree(w: &mut World) -> [Entity; 5] {
+        let [root, a, b, aa, ab] = [(), (), (), (), ()].map(|()| w.spawn(()));
+        w.add_child(root, a).unwrap();
+        w.add_child(root, b).unwrap();
+        w.add_child(a, aa).unwrap();
+        w.add_child(a, ab).unwrap();
+        [root, a, b, aa, ab]
+    }
+
+    #[test]
+    fn traversals() {
+        let mut w = World::new();
+        let [root, a, b, aa, ab] = tree(&mut w);
+        let mut s = IntoSystem::into_system(move |q: HierarchyQuery| {
+            assert_eq!(vec![a, root], q.iter_ancestors(ab).collect::<Vec<_>>());
+            assert_eq!(root, q.root_ancestor(aa));
+            assert_eq!(root, q.root_ancestor(root));
+            assert_eq!(vec![a, aa, ab, b], q.iter_descendants(root).collect::<Vec<_>>());
+            assert_eq!(vec![a, b, aa, ab], q.iter_descendants_breadth_first(root).collect::<Vec<_>>());
+            assert_eq!(0, q.iter_descendants(b).count());
+        });
+        s.run((), &mut w);
+    }
+
+    struct Offset(i32);
+    #[derive(Clone)]
+    struct Position(i32);
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn propagate_positions(mut q: PropagateQuery<Offset, Position>) {
+        propagate(&mut q, |parent, offset| Position(parent.map_or(0, |p| p.0) + offset.0));
+    }
+
+    #[test]
+    fn propagate_offsets() {
+        let mut w = World::new();
+        let [root, a, b, aa, ab] = tree(&mut w);
+        for (e, offset) in [(root, 1), (a, 10), (b, 100), (aa, 1000)] {
+            w.insert(e, (Offset(offset), Position(0))).unwrap();
+        }
+        // ab is not in the query, so it's skipped.
+        let mut s = IntoSystem::into_system(propagate_positions);
+        s.run((), &mut w);
+        let position = |e| w.get::<&Position>(e).unwrap().0;
+        assert_eq!([1, 11, 101, 1011], [root, a, b, aa].map(position));
+        assert!(w.get::<&Position>(ab).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Per component type callbacks, run when a component is added, overwritten or removed
+//! through [`World`] methods or [`Commands`](crate::system::commands::Commands).
+//!
+//! Changes made on the inner `hecs::World` directly don't run hooks. That includes a hecs `CommandBuffer`,
+//! also when it is a `Deferred<CommandBuffer>` system param.
+//! [`ParallelCommands`](crate::system::parallelcommands::ParallelCommands) go through `Commands`, so they do run hooks.
+use core::any::TypeId;
+
+use hecs::{Component, Entity};
+
+use crate::{world::deferredworld::DeferredWorld, World};
+
+/// A component hook. Gets the entity whose component changed.
+pub type ComponentHook = fn(DeferredWorld, Entity);
+
+/// The hooks of one component type, registered with [`World::register_component_hooks`].
+#[derive(Default, Clone, Copy)]
+pub struct ComponentHooks {
+    pub(crate) add: Option<ComponentHook>,
+    pub(crate) insert: Option<ComponentHook>,
+    pub(crate) replace: Option<ComponentHook>,
+    pub(crate) remove: Option<ComponentHook>,
+}
+
+impl ComponentHooks {
+    /// Runs after the component is added to an entity which didn't have it.
+    pub fn on_add(&mut self, hook: ComponentHook) -> &mut Self {
+        assert!(self.add.is_none(), "on_add hook already registered");
+        self.add = Some(hook);
+        self
+    }
+    /// Runs after the component is inserted, whether it was added or overwrote an old value.
+    pub fn on_insert(&mut self, hook: ComponentHook) -> &mut Self {
+        assert!(self.insert.is_none(), "on_insert hook already registered");
+        self.insert = Some(hook);
+        self
+    }
+    /// Runs before the value is overwritten or removed, while the old value can still be read.
+    pub fn on_replace(&mut self, hook: ComponentHook) -> &mut Self {
+        assert!(self.replace.is_none(), "on_replace hook already registered");
+        self.replace = Some(hook);
+        self
+    }
+    /// Runs before the component is removed or its entity despawned, after `on_replace`.
+    pub fn on_remove(&mut self, hook: ComponentHook) -> &mut Self {
+        assert!(self.remove.is_none(), "on_remove hook already registered");
+        self.remove = Some(hook);
+        self
+    }
+}
+
+impl World {
+    /// The hooks of component `T`, to register new ones on. Each hook can only be set once.
+    pub fn register_component_hooks<T: Component>(&mut self) -> &mut ComponentHooks {
+        self.hooks.entry(TypeId::of::<T>()).or_default()
+    }
+
+    /// Runs the hook picked by `kind` of each of `types`.
+    pub(crate) fn run_hooks(&mut self, entity: Entity, types: &[TypeId], kind: fn(&ComponentHooks) -> Option<ComponentHook>) {
+        let hooks: Vec<ComponentHook> = types.iter()
+            .filter_map(|ty| self.hooks.get(ty).and_then(kind))
+            .collect();
+        for hook in hooks {
+            hook(DeferredWorld::new(self), entity);
+        }
+    }
+
+    /// Applies the commands queued by hooks, and the deferred buffers of removal observers.
+    pub(crate) fn flush_commands(&mut self) {
+        while !self.command_queue.is_empty() || !self.pending_observers.is_empty() {
+            for (observer, apply) in core::mem::take(&mut self.pending_observers) {
+                apply(self, observer);
+            }
+            let mut queue = core::mem::take(&mut self.command_queue);
+            queue.apply(self);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_utils::HashMap;
+
+    use crate::{resource::Resource, system::{commands::Commands, parallelcommands::ParallelCommands, IntoSystem, System}};
+
+    use super::*;
+
+    struct Name(&'static str);
+    #[derive(Default, Resource)]
+    struct NameIndex(HashMap<&'static str, Entity>);
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn index_name(world: DeferredWorld, entity: Entity) {
+        let name = world.get::<&Name>(entity).unwrap().0;
+        world.resource_mut::<NameIndex>().0.insert(name, entity);
+    }
+    #[allow(clippy::needless_pass_by_value)]
+    fn unindex_name(world: DeferredWorld, entity: Entity) {
+        let name = world.get::<&Name>(entity).unwrap().0;
+        world.resource_mut::<NameIndex>().0.remove(name);
+    }
+
+    fn index(w: &mut World) -> Vec<(&'static str, Entity)> {
+        let mut index: Vec<_> = w.get_resource::<NameIndex>().0.iter().map(|(n, e)| (*n, *e)).collect();
+        index.sort_unstable();
+        index
+    }
+
+    #[test]
+    fn name_index() {
+        let mut w = World::new();
+        w.insert_resource(NameIndex::default());
+        w.register_component_hooks::<Name>()
+            .on_insert(index_name)
+            .on_replace(unindex_name);
+        let a = w.spawn((Name("a"),));
+        let b = w.spawn((Name("b"),));
+        assert_eq!(vec![("a", a), ("b", b)], index(&mut w));
+        w.insert_one(a, Name("c")).unwrap();
+        assert_eq!(vec![("b", b), ("c", a)], index(&mut w));
+        w.remove_one::<Name>(b).unwrap();
+        w.despawn(a).unwrap();
+        assert!(index(&mut w).is_empty());
+    }
+
+    #[test]
+    fn from_commands() {
+        let mut w = World::new();
+        w.insert_resource(NameIndex::default());
+        w.register_component_hooks::<Name>()
+            .on_insert(index_name)
+            .on_replace(unindex_name);
+        let a = w.spawn((Name("a"),));
+        let mut s = IntoSystem::into_system(move |mut commands: Commands| {
+            commands.spawn((Name("b"),));
+            commands.entity(a).despawn();
+        });
+        s.run((), &mut w);
+        let index = index(&mut w);
+        assert_eq!(1, index.len());
+        assert_eq!("b", index[0].0);
+    }
+
+    #[test]
+    fn from_parallel_commands() {
+        let mut w = World::new();
+        w.insert_resource(NameIndex::default());
+        w.register_component_hooks::<Name>()
+            .on_insert(index_name)
+            .on_replace(unindex_name);
+        let mut s = IntoSystem::into_system(|commands: ParallelCommands| {
+            commands.command_scope(|mut c| { c.spawn((Name("a"),)); });
+        });
+        s.run((), &mut w);
+        assert_eq!("a", index(&mut w)[0].0);
+    }
+
+    struct Health(u32);
+    struct Alive;
+    struct Dead;
+
+    #[test]
+    fn add_and_remove_order() {
+        let mut w = World::new();
+        // add only runs for new components, insert for every write.
+        w.register_component_hooks::<Health>()
+            .on_add(|world, e| world.get::<&mut Health>(e).unwrap().0 += 100)
+            .on_insert(|world, e| world.get::<&mut Health>(e).unwrap().0 += 1)
+            .on_remove(|mut world, e| { world.commands().entity(e).insert((Dead,)); });
+        w.register_component_hooks::<Alive>()
+            .on_remove(|world, e| assert!(world.get::<&Alive>(e).is_ok()));
+        let e = w.spawn((Health(0), Alive));
+        assert_eq!(101, w.get::<&Health>(e).unwrap().0);
+        w.insert_one(e, Health(0)).unwrap();
+        assert_eq!(1, w.get::<&Health>(e).unwrap().0);
+        // structural changes queued by a hook run once the removal is done.
+        w.remove::<(Health, Alive)>(e).unwrap();
+        assert!(w.get::<&Dead>(e).is_ok());
+    }
+}
@@ -1,10 +1,31 @@
+// lets the derive macros name `::bhecs` from inside this crate too.
+extern crate self as bhecs;
+
 pub mod event;
 pub mod system;
 pub mod world;
 pub mod resource;
+pub mod changetick;
+pub mod removal;
+pub mod hooks;
+pub mod observer;
+pub mod hierarchy;
+pub mod relationship;
+pub mod entitymap;
+pub mod typeregistry;
+pub mod reflect;
+pub mod clone;
+pub mod transfer;
+pub mod snapshot;
+pub mod taskpool;
+#[cfg(feature = "serde")]
+pub mod serialize;
+#[cfg(feature = "serde")]
+pub mod scene;
 pub use world::World;
 pub use hecs::PreparedView as View;
-pub use hecs::PreparedQueryIter as Query;
+pub use system::query::queryparam::Query;
+pub use system::query::filter::{With, Without, Or};
 use derive_more::derive::{Deref, DerefMut};
 type ChangeTick = u32;
 
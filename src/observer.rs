@@ -0,0 +1,251 @@
+//! Systems run right away when an [`Event`] is triggered, see [`World::observe`].
+use core::{any::TypeId, marker::PhantomData};
+
+use hecs::{Component, Entity};
+
+use crate::{event::Event, system::{systeminput::SystemInput, BoxedSystem, IntoSystem, System}, ChangeTick, World};
+
+/// The first argument of an observer: the triggered event, and the entity it targets, if any.
+pub struct Trigger<'w, E: Event> {
+    event: &'w E,
+    target: Option<Entity>,
+}
+
+impl<'w, E: Event> Trigger<'w, E> {
+    #[must_use]
+    pub fn event(&self) -> &'w E { self.event }
+    /// The entity the event was triggered for. `None` for untargeted triggers.
+    #[must_use]
+    pub fn target(&self) -> Option<Entity> { self.target }
+}
+
+impl<E: Event> SystemInput for Trigger<'_, E> {
+    type Param<'i> = Trigger<'i, E>;
+    type Inner<'i> = Trigger<'i, E>;
+
+    fn wrap(this: Self::Inner<'_>) -> Self::Param<'_> { this }
+}
+
+/// Triggered for an entity after it gets a `T` it didn't have.
+pub struct OnAdd<T: Component>(PhantomData<fn() -> T>);
+/// Triggered for an entity before it loses its `T`, by removal or despawn.
+/// Structural changes from the observer's deferred buffers are applied after the removal.
+pub struct OnRemove<T: Component>(PhantomData<fn() -> T>);
+
+impl<T: Component> Default for OnAdd<T> {
+    fn default() -> Self { Self(PhantomData) }
+}
+impl<T: Component> Default for OnRemove<T> {
+    fn default() -> Self { Self(PhantomData) }
+}
+impl<T: Component> Event for OnAdd<T> {
+    fn on_observe(world: &mut World) {
+        world.lifecycle.entry(TypeId::of::<T>()).or_default().add = Some(trigger_lifecycle::<Self>);
+    }
+}
+impl<T: Component> Event for OnRemove<T> {
+    fn on_observe(world: &mut World) {
+        world.lifecycle.entry(TypeId::of::<T>()).or_default().remove = Some(trigger_removal::<Self>);
+    }
+}
+
+/// Runs the observers of one lifecycle event for an entity.
+pub(crate) type LifecycleTrigger = fn(&mut World, Entity);
+
+/// Triggers for the lifecycle events of one component type, set once they are observed.
+#[derive(Default, Clone, Copy)]
+pub(crate) struct LifecycleTriggers {
+    pub(crate) add: Option<LifecycleTrigger>,
+    pub(crate) remove: Option<LifecycleTrigger>,
+}
+
+fn trigger_lifecycle<E: Event + Default>(world: &mut World, entity: Entity) {
+    world.trigger_targets(E::default(), [entity]);
+}
+/// Runs the observers before a removal, but leaves their deferred buffers for after it.
+fn trigger_removal<E: Event + Default>(world: &mut World, entity: Entity) {
+    let event = E::default();
+    for observer in world.observers_of::<E>() {
+        run_observer(world, observer, Trigger { event: &event, target: Some(entity) }, false);
+        world.pending_observers.push((observer, apply_observer::<E>));
+    }
+}
+
+/// Component of an observer entity.
+struct Observer<E: Event> {
+    system: Option<BoxedSystem<Trigger<'static, E>>>,
+}
+
+fn run_observer<E: Event>(world: &mut World, observer: Entity, trigger: Trigger<'_, E>, apply: bool) {
+    let Some(mut system) = world.hworld.get::<&mut Observer<E>>(observer).ok().and_then(|mut o| o.system.take()) else {
+        // despawned by an earlier observer, or triggered by itself.
+        return;
+    };
+    if !system.is_initialized(world) {
+        system.initialize(world);
+    }
+    world.check_change_ticks();
+    system.check_change_tick(world.change_tick());
+    system.run_unchecked(trigger, world);
+    if apply {
+        system.apply_deferred(world);
+    }
+    if let Ok(mut o) = world.hworld.get::<&mut Observer<E>>(observer) {
+        o.system = Some(system);
+    }
+}
+fn check_observers<E: Event>(world: &mut World, change_tick: ChangeTick) {
+    for (_, observer) in world.hworld.query_mut::<&mut Observer<E>>() {
+        // a running observer is checked by run_observer.
+        if let Some(system) = observer.system.as_mut() {
+            system.check_change_tick(change_tick);
+        }
+    }
+}
+fn apply_observer<E: Event>(world: &mut World, observer: Entity) {
+    let Some(mut system) = world.hworld.get::<&mut Observer<E>>(observer).ok().and_then(|mut o| o.system.take()) else {
+        return;
+    };
+    system.apply_deferred(world);
+    if let Ok(mut o) = world.hworld.get::<&mut Observer<E>>(observer) {
+        o.system = Some(system);
+    }
+}
+
+impl World {
+    /// Adds an observer, a system whose first argument is a [`Trigger<E>`].
+    /// It runs every time `E` is triggered. Despawn the returned entity to remove it.
+    pub fn observe<E: Event, M>(&mut self, system: impl IntoSystem<Trigger<'static, E>, (), M>) -> Entity {
+        E::on_observe(self);
+        let system: BoxedSystem<Trigger<'static, E>> = Box::new(IntoSystem::into_system(system));
+        self.tick_checks.entry(TypeId::of::<Observer<E>>()).or_insert(check_observers::<E>);
+        self.spawn((Observer { system: Some(system) },))
+    }
+
+    /// Runs the observers of `E` once, without a target.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn trigger<E: Event>(&mut self, event: E) {
+        for observer in self.observers_of::<E>() {
+            run_observer(self, observer, Trigger { event: &event, target: None }, true);
+        }
+    }
+
+    /// Runs the observers of `E` once for every target, applying their deferred buffers right away.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn trigger_targets<E: Event>(&mut self, event: E, targets: impl IntoIterator<Item = Entity>) {
+        let observers = self.observers_of::<E>();
+        for target in targets {
+            for &observer in &observers {
+                run_observer(self, observer, Trigger { event: &event, target: Some(target) }, true);
+            }
+        }
+    }
+
+    fn observers_of<E: Event>(&self) -> Vec<Entity> {
+        self.hworld.query::<&Observer<E>>().iter().map(|(e, _)| e).collect()
+    }
+
+    /// Runs the lifecycle triggers picked by `kind` of each of `types`.
+    pub(crate) fn run_lifecycle(&mut self, entity: Entity, types: &[TypeId], kind: fn(&LifecycleTriggers) -> Option<LifecycleTrigger>) {
+        let triggers: Vec<LifecycleTrigger> = types.iter()
+            .filter_map(|ty| self.lifecycle.get(ty).and_then(kind))
+            .collect();
+        for trigger in triggers {
+            trigger(self, entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resource::Resource, system::commands::Commands, Query};
+
+    use super::*;
+
+    struct Health(u32);
+    #[derive(Resource)]
+    struct Log(Vec<String>);
+
+    struct Damage(u32);
+    impl Event for Damage {}
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn log_add(trigger: Trigger<OnAdd<Health>>, q: Query<&Health>, log: &mut Log) {
+        let health = q.get(trigger.target().unwrap()).unwrap().0;
+        log.0.push(format!("add {health}"));
+    }
+    #[allow(clippy::needless_pass_by_value)]
+    fn log_remove(trigger: Trigger<OnRemove<Health>>, q: Query<&Health>, log: &mut Log, mut commands: Commands) {
+        let target = trigger.target().unwrap();
+        // the component is still there, and commands wait until it's gone.
+        log.0.push(format!("remove {}", q.get(target).unwrap().0));
+        commands.entity(target).insert((Damage(0),));
+    }
+    #[allow(clippy::needless_pass_by_value)]
+    fn apply_damage(trigger: Trigger<Damage>, mut q: Query<&mut Health>) {
+        if let Some(target) = trigger.target() {
+            q.get_mut(target).unwrap().0 -= trigger.event().0;
+        }
+    }
+
+    #[test]
+    fn lifecycle() {
+        let mut w = World::new();
+        w.insert_resource(Log(Vec::new()));
+        w.observe(log_add);
+        w.observe(log_remove);
+        let e = w.spawn((Health(5),));
+        w.insert_one(e, Health(7)).unwrap();
+        w.remove_one::<Health>(e).unwrap();
+        assert!(w.get::<&Damage>(e).is_ok());
+        let other = w.spawn((Health(1),));
+        w.despawn(other).unwrap();
+        assert_eq!(vec!["add 5", "remove 7", "add 1", "remove 1"], w.get_resource::<Log>().0);
+    }
+
+    #[test]
+    fn take_applies_after() {
+        let mut w = World::new();
+        w.insert_resource(Log(Vec::new()));
+        w.observe(|trigger: Trigger<OnRemove<Health>>, mut commands: Commands| {
+            let target = trigger.target().unwrap();
+            commands.add(move |world| {
+                let alive = world.contains(target);
+                world.get_resource_mut::<Log>().0.push(format!("alive {alive}"));
+            });
+        });
+        let e = w.spawn((Health(3),));
+        let mut other = hecs::World::new();
+        let moved = other.spawn(w.take(e).unwrap());
+        assert_eq!(3, other.get::<&Health>(moved).unwrap().0);
+        assert_eq!(vec!["alive false"], w.get_resource::<Log>().0);
+    }
+
+    #[test]
+    fn user_events() {
+        let mut w = World::new();
+        w.insert_resource(Log(Vec::new()));
+        let observer = w.observe(apply_damage);
+        let a = w.spawn((Health(10),));
+        let b = w.spawn((Health(10),));
+        w.trigger_targets(Damage(3), [a, b]);
+        w.trigger_targets(Damage(2), [a]);
+        w.trigger(Damage(100));
+        assert_eq!(5, w.get::<&Health>(a).unwrap().0);
+        assert_eq!(7, w.get::<&Health>(b).unwrap().0);
+        w.despawn(observer).unwrap();
+        w.trigger_targets(Damage(1), [a]);
+        assert_eq!(5, w.get::<&Health>(a).unwrap().0);
+    }
+
+    #[test]
+    fn deferred_applied_immediately() {
+        let mut w = World::new();
+        w.insert_resource(Log(Vec::new()));
+        w.observe(|trigger: Trigger<Damage>, mut commands: Commands| {
+            commands.spawn((Health(trigger.event().0),));
+        });
+        w.trigger(Damage(4));
+        assert_eq!(vec![4], w.query::<&Health>().iter().map(|(_, h)| h.0).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,226 @@
+//! Reading and writing fields of components by name at runtime, e.g. from a debug console.
+//!
+//! Structs derive [`Reflect`] to expose their fields, and are found by registered name through
+//! [`World::reflect_component`] once registered with [`RegisterType::with_reflect`].
+use hecs::{Component, ComponentError, Entity, RefMut};
+
+use crate::{typeregistry::{RegisterType, TypeRegistry}, World};
+pub use bhecs_macros::Reflect;
+
+/// The value of a reflected field, like a number or string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    String(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReflectError {
+    /// No type of that name is registered with [`RegisterType::with_reflect`].
+    UnknownType(String),
+    NoSuchEntity,
+    MissingComponent,
+    NoSuchField(String),
+    /// The path leads to a struct, not a value.
+    NotAValue,
+    /// The value doesn't fit the field, like a string for a number or `-1` for a `u32`.
+    WrongType,
+}
+
+/// A struct whose fields can be listed and accessed by name, or a value which can be
+/// read and set as a [`DynamicValue`]. Structs derive it, exposing their fields, which must implement it too.
+/// Tuple struct fields are named `0`, `1` and so on, and `#[reflect(ignore)]` leaves a field out:
+///
+/// ```
+/// # use bhecs::reflect::{DynamicValue, Reflect};
+/// #[derive(Reflect)]
+/// struct Health { current: u32, max: u32, #[reflect(ignore)] last_hit: Option<std::time::Instant> }
+/// #[derive(Reflect)]
+/// struct Position(f32, f32);
+///
+/// let mut health = Health { current: 5, max: 10, last_hit: None };
+/// let reflected: &mut dyn Reflect = &mut health;
+/// assert_eq!(&["current", "max"], reflected.fields());
+/// reflected.set_path("current", DynamicValue::Int(7)).unwrap();
+/// assert_eq!(7, health.current);
+/// ```
+pub trait Reflect: 'static {
+    /// Names of the fields. Empty for values.
+    fn fields(&self) -> &'static [&'static str] { &[] }
+    #[allow(unused_variables)]
+    fn field(&self, name: &str) -> Option<&dyn Reflect> { None }
+    #[allow(unused_variables)]
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> { None }
+    /// `None` for structs.
+    fn value(&self) -> Option<DynamicValue> { None }
+    #[allow(unused_variables)]
+    fn set_value(&mut self, value: DynamicValue) -> Result<(), ReflectError> { Err(ReflectError::NotAValue) }
+}
+
+impl dyn Reflect {
+    /// Follows a path of field names separated by dots, like `stats.speed`. An empty path is `self`.
+    ///
+    /// # Errors
+    /// If a field doesn't exist.
+    pub fn path(&self, path: &str) -> Result<&dyn Reflect, ReflectError> {
+        let mut current = self;
+        for name in path.split('.').filter(|name| !name.is_empty()) {
+            current = current.field(name).ok_or_else(|| ReflectError::NoSuchField(name.to_owned()))?;
+        }
+        Ok(current)
+    }
+    /// # Errors
+    /// If a field doesn't exist.
+    pub fn path_mut(&mut self, path: &str) -> Result<&mut dyn Reflect, ReflectError> {
+        let mut current = self;
+        for name in path.split('.').filter(|name| !name.is_empty()) {
+            current = current.field_mut(name).ok_or_else(|| ReflectError::NoSuchField(name.to_owned()))?;
+        }
+        Ok(current)
+    }
+    /// # Errors
+    /// If a field doesn't exist or the path leads to a struct.
+    pub fn get_path(&self, path: &str) -> Result<DynamicValue, ReflectError> {
+        self.path(path)?.value().ok_or(ReflectError::NotAValue)
+    }
+    /// # Errors
+    /// If a field doesn't exist, the path leads to a struct or the value doesn't fit.
+    pub fn set_path(&mut self, path: &str, value: DynamicValue) -> Result<(), ReflectError> {
+        self.path_mut(path)?.set_value(value)
+    }
+}
+
+macro_rules! impl_reflect_int {
+    ($($ty: ty),*) => {
+        $(
+            impl Reflect for $ty {
+                fn value(&self) -> Option<DynamicValue> {
+                    i128::try_from(*self).ok().map(DynamicValue::Int)
+                }
+                fn set_value(&mut self, value: DynamicValue) -> Result<(), ReflectError> {
+                    let DynamicValue::Int(value) = value else { return Err(ReflectError::WrongType) };
+                    *self = <$ty>::try_from(value).map_err(|_| ReflectError::WrongType)?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+impl_reflect_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! impl_reflect_float {
+    ($($ty: ty),*) => {
+        $(
+            impl Reflect for $ty {
+                fn value(&self) -> Option<DynamicValue> {
+                    Some(DynamicValue::Float(f64::from(*self)))
+                }
+                // typed in numbers don't need to be exact.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+                fn set_value(&mut self, value: DynamicValue) -> Result<(), ReflectError> {
+                    *self = match value {
+                        DynamicValue::Float(value) => value as $ty,
+                        DynamicValue::Int(value) => value as $ty,
+                        _ => return Err(ReflectError::WrongType),
+                    };
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+impl_reflect_float!(f32, f64);
+
+impl Reflect for bool {
+    fn value(&self) -> Option<DynamicValue> { Some(DynamicValue::Bool(*self)) }
+    fn set_value(&mut self, value: DynamicValue) -> Result<(), ReflectError> {
+        let DynamicValue::Bool(value) = value else { return Err(ReflectError::WrongType) };
+        *self = value;
+        Ok(())
+    }
+}
+impl Reflect for String {
+    fn value(&self) -> Option<DynamicValue> { Some(DynamicValue::String(self.clone())) }
+    fn set_value(&mut self, value: DynamicValue) -> Result<(), ReflectError> {
+        let DynamicValue::String(value) = value else { return Err(ReflectError::WrongType) };
+        *self = value;
+        Ok(())
+    }
+}
+
+/// Borrows the component of an entity as [`Reflect`].
+pub(crate) type ReflectFn = for<'a> fn(&'a hecs::World, Entity) -> Result<RefMut<'a, dyn Reflect>, ComponentError>;
+
+impl<T: Component + Reflect> RegisterType<'_, T> {
+    /// Lets `T` be found by [`World::reflect_component`].
+    #[must_use]
+    pub fn with_reflect(self) -> Self {
+        self.registration.reflect = Some(|world, entity| {
+            let component = world.get::<&mut T>(entity)?;
+            Ok(RefMut::map(component, |component| component as &mut dyn Reflect))
+        });
+        self
+    }
+}
+
+impl World {
+    /// Borrows the component registered as `name` of `entity`, to access its fields by name.
+    ///
+    /// # Errors
+    /// If the type isn't registered with [`RegisterType::with_reflect`], or the entity doesn't have it.
+    ///
+    /// # Panics
+    /// If the component is already borrowed.
+    pub fn reflect_component(&self, entity: Entity, name: &str) -> Result<RefMut<'_, dyn Reflect>, ReflectError> {
+        let reflect = self.hworld.get::<&TypeRegistry>(self.resource_entity()).ok()
+            .and_then(|registry| registry.get_by_name(name)?.reflect)
+            .ok_or_else(|| ReflectError::UnknownType(name.to_owned()))?;
+        reflect(&self.hworld, entity).map_err(|error| match error {
+            ComponentError::NoSuchEntity => ReflectError::NoSuchEntity,
+            ComponentError::MissingComponent(_) => ReflectError::MissingComponent,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Reflect)]
+    struct Health { current: u32, max: u32 }
+    #[derive(Reflect)]
+    struct Stats { health: Health, speed: f32, name: String }
+    #[derive(Reflect)]
+    struct Position(f32, f32);
+
+    #[test]
+    fn paths() {
+        let mut stats = Stats { health: Health { current: 3, max: 10 }, speed: 1.5, name: "orc".into() };
+        let reflected: &mut dyn Reflect = &mut stats;
+        assert_eq!(&["health", "speed", "name"], reflected.fields());
+        assert_eq!(&["current", "max"], reflected.path("health").unwrap().fields());
+        assert_eq!(Ok(DynamicValue::Int(10)), reflected.get_path("health.max"));
+        reflected.set_path("health.current", DynamicValue::Int(4)).unwrap();
+        reflected.set_path("speed", DynamicValue::Int(2)).unwrap();
+        assert_eq!(Err(ReflectError::WrongType), reflected.set_path("health.max", DynamicValue::Int(-1)));
+        assert_eq!(Err(ReflectError::WrongType), reflected.set_path("name", DynamicValue::Bool(true)));
+        assert_eq!(Err(ReflectError::NotAValue), reflected.get_path("health"));
+        assert_eq!(Err(ReflectError::NoSuchField("mana".into())), reflected.get_path("health.mana"));
+        assert_eq!(4, stats.health.current);
+        assert!((stats.speed - 2.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn world_lookup() {
+        let mut w = World::new();
+        let _ = w.type_registry_mut().register_named::<Position>("Position").with_reflect();
+        let e = w.spawn((Position(1.0, 2.0),));
+        let bare = w.spawn(());
+        w.reflect_component(e, "Position").unwrap().set_path("1", DynamicValue::Float(5.0)).unwrap();
+        assert_eq!(Ok(DynamicValue::Float(5.0)), w.reflect_component(e, "Position").unwrap().get_path("1"));
+        assert_eq!(Some(ReflectError::MissingComponent), w.reflect_component(bare, "Position").err());
+        assert_eq!(Some(ReflectError::UnknownType("Health".into())), w.reflect_component(e, "Health").err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Relationships from a source entity to a target entity, like `Likes(entity)`.
+//!
+//! The source holds a [`Relationship`] component, and the target gets a [`RelationshipTarget`]
+//! listing all its sources. After [`World::register_relationship`], component hooks keep the list
+//! up to date for every [`World`] method and [`Commands`](crate::system::commands::Commands).
+//! Query the sources of one target with the [`Targeting`](crate::system::query::filter::Targeting) filter and
+//! [`Query::iter_targeting`](crate::Query::iter_targeting). [`Parent`](crate::hierarchy::Parent) is a relationship too.
+use core::any::TypeId;
+
+use bevy_utils::{tracing::warn, HashSet};
+use hecs::{Component, Entity};
+
+use crate::{entitymap::EntityMap, typeregistry::MapEntitiesFn, world::deferredworld::DeferredWorld, World};
+
+/// What happens to the sources of a target when it is despawned, or loses its [`RelationshipTarget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DespawnPolicy {
+    /// Despawn the sources too.
+    Cascade,
+    /// Remove the relationship from the sources.
+    Detach,
+    /// Leave the sources pointing at the missing target.
+    Nothing,
+}
+
+/// A component on the source entity, pointing at the target entity.
+pub trait Relationship: Component {
+    /// The component listing the sources on the target.
+    type Target: RelationshipTarget;
+    const DESPAWN_POLICY: DespawnPolicy = DespawnPolicy::Detach;
+
+    fn target(&self) -> Entity;
+    /// Points at `target` instead, without updating either list. Only used to remap references,
+    /// e.g. when moving entities to another world.
+    fn set_target(&mut self, target: Entity);
+}
+
+/// A component on the target entity, listing the sources of a [`Relationship`].
+/// Never empty, it is removed with the last source.
+pub trait RelationshipTarget: Component {
+    fn from_sources(sources: Vec<Entity>) -> Self;
+    fn sources(&self) -> &[Entity];
+    fn sources_mut(&mut self) -> &mut Vec<Entity>;
+}
+
+/// What the world knows of a registered [`Relationship`], keyed by its [`TypeId`].
+#[derive(Clone, Copy)]
+pub(crate) struct RelationshipFns {
+    /// The [`TypeId`] of its [`RelationshipTarget`].
+    pub(crate) target: TypeId,
+    /// Remaps the relationship and the target list of an entity.
+    pub(crate) map_entities: MapEntitiesFn,
+    /// Detaches the given entities from the entities outside them, in both directions.
+    pub(crate) detach: fn(&mut World, &HashSet<Entity>),
+}
+
+impl World {
+    /// Registers the hooks which maintain `R::Target`, and apply `R::DESPAWN_POLICY`.
+    ///
+    /// # Panics
+    /// If `R` or `R::Target` already has an on_insert, on_replace or on_remove hook.
+    pub fn register_relationship<R: Relationship>(&mut self) {
+        self.register_component_hooks::<R>()
+            .on_insert(on_source_insert::<R>)
+            .on_replace(on_source_replace::<R>);
+        self.register_component_hooks::<R::Target>()
+            .on_remove(on_target_remove::<R>);
+        self.relationships.insert(TypeId::of::<R>(), RelationshipFns {
+            target: TypeId::of::<R::Target>(),
+            map_entities: map_entities::<R>,
+            detach: detach::<R>,
+        });
+    }
+    /// True if `type_id` is the [`RelationshipTarget`] of a registered relationship.
+    pub(crate) fn is_relationship_target(&self, type_id: TypeId) -> bool {
+        self.relationships.values().any(|relationship| relationship.target == type_id)
+    }
+
+    /// The sources pointing at `target` through `R`.
+    #[must_use]
+    pub fn related<R: Relationship>(&self, target: Entity) -> Vec<Entity> {
+        self.hworld.get::<&R::Target>(target).map(|t| t.sources().to_vec()).unwrap_or_default()
+    }
+
+    fn add_source<R: Relationship>(&mut self, target: Entity, source: Entity) {
+        if !self.hworld.contains(target) {
+            warn!("{source:?} is related to {target:?}, which does not exist");
+            return;
+        }
+        let added = self.hworld.get::<&mut R::Target>(target).map(|mut t| {
+            // already listed when the source was spawned with a list which had it, e.g. a copied hierarchy.
+            if !t.sources().contains(&source) {
+                t.sources_mut().push(source);
+            }
+        });
+        if added.is_err() {
+            let _ = self.insert_one(target, R::Target::from_sources(vec![source]));
+        }
+    }
+    fn remove_source<R: Relationship>(&mut self, target: Entity, source: Entity) {
+        let now_empty = self.hworld.get::<&mut R::Target>(target).is_ok_and(|mut t| {
+            t.sources_mut().retain(|&s| s != source);
+            t.sources().is_empty()
+        });
+        if now_empty {
+            let _ = self.remove_one::<R::Target>(target);
+        }
+    }
+}
+
+fn map_entities<R: Relationship>(world: &hecs::World, entity: Entity, map: &EntityMap) {
+    if let Ok(mut relationship) = world.get::<&mut R>(entity) {
+        let target = map.map(relationship.target());
+        relationship.set_target(target);
+    }
+    if let Ok(mut target) = world.get::<&mut R::Target>(entity) {
+        for source in target.sources_mut() {
+            *source = map.map(*source);
+        }
+    }
+}
+fn detach<R: Relationship>(world: &mut World, entities: &HashSet<Entity>) {
+    for &entity in entities {
+        let target = world.hworld.get::<&R>(entity).map(|relationship| relationship.target());
+        if target.is_ok_and(|target| !entities.contains(&target)) {
+            let _ = world.remove_one::<R>(entity);
+        }
+        for source in world.related::<R>(entity) {
+            if !entities.contains(&source) {
+                let _ = world.remove_one::<R>(source);
+            }
+        }
+    }
+}
+
+fn on_source_insert<R: Relationship>(mut world: DeferredWorld, source: Entity) {
+    let target = world.get::<&R>(source).unwrap().target();
+    world.commands().add(move |world| world.add_source::<R>(target, source));
+}
+fn on_source_replace<R: Relationship>(mut world: DeferredWorld, source: Entity) {
+    let target = world.get::<&R>(source).unwrap().target();
+    world.commands().add(move |world| world.remove_source::<R>(target, source));
+}
+fn on_target_remove<R: Relationship>(mut world: DeferredWorld, target: Entity) {
+    let sources = world.get::<&R::Target>(target).unwrap().sources().to_vec();
+    let mut commands = world.commands();
+    for source in sources {
+        match R::DESPAWN_POLICY {
+            DespawnPolicy::Cascade => commands.add(move |world| {
+                let _ = world.despawn(source);
+            }),
+            DespawnPolicy::Detach => {
+                commands.entity(source).remove::<(R,)>();
+            }
+            DespawnPolicy::Nothing => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{system::{query::filter::Targeting, IntoSystem, System}, Query};
+
+    use super::*;
+
+    struct Likes(Entity);
+    struct LikedBy(Vec<Entity>);
+    impl Relationship for Likes {
+        type Target = LikedBy;
+
+        fn target(&self) -> Entity { self.0 }
+        fn set_target(&mut self, target: Entity) { self.0 = target; }
+    }
+    impl RelationshipTarget for LikedBy {
+        fn from_sources(sources: Vec<Entity>) -> Self { Self(sources) }
+        fn sources(&self) -> &[Entity] { &self.0 }
+        fn sources_mut(&mut self) -> &mut Vec<Entity> { &mut self.0 }
+    }
+
+    struct OwnedBy(Entity);
+    struct Owns(Vec<Entity>);
+    impl Relationship for OwnedBy {
+        type Target = Owns;
+        const DESPAWN_POLICY: DespawnPolicy = DespawnPolicy::Cascade;
+
+        fn target(&self) -> Entity { self.0 }
+        fn set_target(&mut self, target: Entity) { self.0 = target; }
+    }
+    impl RelationshipTarget for Owns {
+        fn from_sources(sources: Vec<Entity>) -> Self { Self(sources) }
+        fn sources(&self) -> &[Entity] { &self.0 }
+        fn sources_mut(&mut self) -> &mut Vec<Entity> { &mut self.0 }
+    }
+
+    struct Name(&'static str);
+
+    #[test]
+    fn reverse_list_is_maintained() {
+        let mut w = World::new();
+        w.register_relationship::<Likes>();
+        let [a, b, c] = [(), (), ()].map(|()| w.spawn(()));
+        w.insert_one(b, Likes(a)).unwrap();
+        w.insert_one(c, Likes(a)).unwrap();
+        assert_eq!(vec![b, c], w.related::<Likes>(a));
+        w.insert_one(b, Likes(c)).unwrap();
+        assert_eq!(vec![c], w.related::<Likes>(a));
+        assert_eq!(vec![b], w.related::<Likes>(c));
+        w.despawn(c).unwrap();
+        assert!(w.get::<&LikedBy>(a).is_err());
+        // detached from the despawned target.
+        assert!(w.get::<&Likes>(b).is_err());
+    }
+
+    #[test]
+    fn cascade() {
+        let mut w = World::new();
+        w.register_relationship::<OwnedBy>();
+        let owner = w.spawn(());
+        let item = w.spawn((OwnedBy(owner),));
+        let part = w.spawn((OwnedBy(item),));
+        w.despawn(owner).unwrap();
+        assert!(!w.contains(item) && !w.contains(part));
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn names_liking(targets: Query<(&Name, &LikedBy)>, names: Query<&Name>) -> Vec<(&'static str, &'static str)> {
+        let mut pairs = Vec::new();
+        for (_, (target, liked_by)) in targets.iter() {
+            pairs.extend(names.iter_many(liked_by.sources()).map(|(_, source)| (source.0, target.0)));
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn query_by_target() {
+        let mut w = World::new();
+        w.register_relationship::<Likes>();
+        let alice = w.spawn((Name("alice"),));
+        let bob = w.spawn((Name("bob"),));
+        w.spawn((Name("carol"), Likes(bob)));
+        w.insert_one(alice, Likes(bob)).unwrap();
+        w.insert_one(bob, Likes(alice)).unwrap();
+        let mut s = IntoSystem::into_system(names_liking);
+        assert_eq!(vec![("alice", "bob"), ("bob", "alice"), ("carol", "bob")], s.run((), &mut w));
+    }
+
+    #[test]
+    fn targeting_filter() {
+        let mut w = World::new();
+        w.register_relationship::<Likes>();
+        let alice = w.spawn((Name("alice"),));
+        let bob = w.spawn((Name("bob"), Likes(alice)));
+        w.spawn((Name("carol"), Likes(bob)));
+        w.spawn((Name("dave"), Likes(alice)));
+        let mut s = IntoSystem::into_system(move |q: Query<&Name, Targeting<Likes>>| {
+            q.iter_targeting::<Likes>(alice).map(|(_, name)| name.0).collect::<Vec<_>>()
+        });
+        assert_eq!(vec!["bob", "dave"], s.run((), &mut w));
+    }
+}
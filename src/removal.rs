@@ -0,0 +1,201 @@
+use core::{any::TypeId, marker::PhantomData};
+
+use hecs::{Component, Entity};
+
+use crate::{system::{systemmeta::SystemMeta, systemparam::SystemParam}, ChangeTick, World};
+
+/// Entities which lost a component of one type.
+/// Only kept for types some [`RemovedComponents`] param asked for.
+#[derive(Default)]
+pub(crate) struct RemovedLog {
+    entities: Vec<Entity>,
+    /// How many entities were dropped from the front of `entities` so far.
+    start: usize,
+    /// Length of `entities` at the previous [`World::clear_trackers`].
+    previous: usize,
+}
+impl RemovedLog {
+    pub(crate) fn push(&mut self, entity: Entity) {
+        self.entities.push(entity);
+    }
+    fn clear(&mut self) {
+        self.entities.drain(..self.previous);
+        self.start += self.previous;
+        self.previous = self.entities.len();
+    }
+    fn end(&self) -> usize { self.start + self.entities.len() }
+    fn unread(&self, cursor: usize) -> &[Entity] {
+        &self.entities[cursor.max(self.start) - self.start..]
+    }
+}
+
+impl World {
+    /// The types of `entity` which removals are recorded for.
+    pub(crate) fn tracked_types(&self, entity: Entity) -> Vec<TypeId> {
+        if self.removed.is_empty() {
+            return Vec::new();
+        }
+        self.hworld.entity(entity)
+            .map(|e| e.component_types().filter(|ty| self.removed.contains_key(ty)).collect())
+            .unwrap_or_default()
+    }
+    /// Records the types in `before` which `entity` doesn't have anymore.
+    pub(crate) fn record_removed(&mut self, entity: Entity, before: &[TypeId]) {
+        let after = self.tracked_types(entity);
+        for ty in before.iter().filter(|ty| !after.contains(ty)) {
+            if let Some(log) = self.removed.get_mut(ty) {
+                log.push(entity);
+            }
+        }
+    }
+
+    /// Entities which lost a `T` and are still recorded.
+    /// Empty unless some [`RemovedComponents<T>`] param was initialized.
+    pub fn removed<T: Component>(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.removed.get(&TypeId::of::<T>()).into_iter().flat_map(|log| log.entities.iter().copied())
+    }
+
+    /// Forgets removals recorded before the previous call, so each is kept for two calls.
+    /// Meant to be called once per frame, otherwise the records grow forever.
+    pub fn clear_trackers(&mut self) {
+        for log in self.removed.values_mut() {
+            log.clear();
+        }
+    }
+}
+
+/// Entities which lost a `T`, by removal or despawn, since this system last read them.
+///
+/// Each system has its own cursor, so every system sees every removal once,
+/// as long as it runs before the record is dropped by [`World::clear_trackers`].
+pub struct RemovedComponents<'w, 's, T: Component> {
+    log: &'w RemovedLog,
+    cursor: &'s mut usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> RemovedComponents<'_, '_, T> {
+    /// Returns the unread entities and marks them read.
+    pub fn read(&mut self) -> impl Iterator<Item = Entity> + '_ {
+        let unread = self.log.unread(*self.cursor);
+        *self.cursor = self.log.end();
+        unread.iter().copied()
+    }
+    #[must_use]
+    pub fn len(&self) -> usize { self.log.unread(*self.cursor).len() }
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    /// Marks everything read, without looking at it.
+    pub fn clear(&mut self) {
+        *self.cursor = self.log.end();
+    }
+}
+
+impl<T: Component> SystemParam for RemovedComponents<'_, '_, T> {
+    type State = usize;
+
+    type Item<'world, 'state> = RemovedComponents<'world, 'state, T>;
+
+    fn init_state(world: &mut World, _: &mut SystemMeta) -> Self::State {
+        world.removed.entry(TypeId::of::<T>()).or_default();
+        0
+    }
+
+    fn get_param<'world, 'state>(
+        state: &'state mut Self::State,
+        _: &SystemMeta,
+        world: &'world World,
+        _: ChangeTick,
+    ) -> Self::Item<'world, 'state> {
+        let log = world.removed.get(&TypeId::of::<T>()).expect("registered in init_state");
+        RemovedComponents { log, cursor: state, marker: PhantomData }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resource::Resource, system::{IntoSystem, System}};
+
+    use super::*;
+
+    struct Handle(u32);
+    struct Other;
+
+    fn removed_handles(mut removed: RemovedComponents<Handle>) -> Vec<Entity> {
+        removed.read().collect()
+    }
+
+    #[test]
+    fn remove_and_despawn() {
+        let mut w = World::new();
+        let mut s = IntoSystem::into_system(removed_handles);
+        let a = w.spawn((Handle(1), Other));
+        let b = w.spawn((Handle(2),));
+        let c = w.spawn((Other,));
+        assert!(s.run((), &mut w).is_empty());
+        assert_eq!(1, w.remove_one::<Handle>(a).unwrap().0);
+        w.despawn(b).unwrap();
+        w.despawn(c).unwrap();
+        assert_eq!(vec![a, b], s.run((), &mut w));
+        assert!(s.run((), &mut w).is_empty());
+        // removing something else doesn't count.
+        w.remove_one::<Other>(a).unwrap();
+        assert!(s.run((), &mut w).is_empty());
+    }
+
+    #[test]
+    fn cursor_per_system() {
+        let mut w = World::new();
+        let mut first = IntoSystem::into_system(removed_handles);
+        let mut second = IntoSystem::into_system(removed_handles);
+        first.initialize(&mut w);
+        second.initialize(&mut w);
+        let a = w.spawn((Handle(1),));
+        let b = w.spawn((Handle(2),));
+        w.despawn(a).unwrap();
+        assert_eq!(vec![a], first.run((), &mut w));
+        w.take(b).unwrap();
+        assert_eq!(vec![b], first.run((), &mut w));
+        assert_eq!(vec![a, b], second.run((), &mut w));
+    }
+
+    #[test]
+    fn kept_for_two_clears() {
+        let mut w = World::new();
+        let mut s = IntoSystem::into_system(removed_handles);
+        s.initialize(&mut w);
+        let a = w.spawn((Handle(1),));
+        let b = w.spawn((Handle(2),));
+        w.despawn(a).unwrap();
+        w.clear_trackers();
+        w.despawn(b).unwrap();
+        assert_eq!(vec![a, b], w.removed::<Handle>().collect::<Vec<_>>());
+        w.clear_trackers();
+        assert_eq!(vec![b], w.removed::<Handle>().collect::<Vec<_>>());
+        assert_eq!(vec![b], s.run((), &mut w));
+        w.clear_trackers();
+        assert_eq!(0, w.removed::<Handle>().count());
+        assert!(s.run((), &mut w).is_empty());
+    }
+
+    #[derive(Resource)]
+    struct Score(u32);
+
+    #[test]
+    fn clear_keeps_resources() {
+        let mut w = World::new();
+        w.insert_resource(Score(1));
+        w.spawn((Handle(1),));
+        w.clear();
+        assert_eq!(1, w.len());
+        assert_eq!(1, w.get_resource::<Score>().0);
+        w.insert_resource(Score(2));
+        // once Handle removals are tracked, clear despawns its entities one by one to record them.
+        let mut s = IntoSystem::into_system(removed_handles);
+        s.initialize(&mut w);
+        let a = w.spawn((Handle(3),));
+        w.clear();
+        assert_eq!(vec![a], s.run((), &mut w));
+        assert_eq!(2, w.get_resource::<Score>().0);
+    }
+}
@@ -1,13 +1,19 @@
 use hecs::{Component, PreparedQuery, PreparedQueryBorrow, PreparedQueryIter, PreparedView};
 
 use crate::{system::systemparam::SystemParam, World};
+/// Marks a type as a resource, so `&R` and `&mut R` can be system params.
+/// Usually implemented with `#[derive(Resource)]`.
+///
+/// Not implemented for every component, since `World` itself is one and
+/// `&mut World` must stay an exclusive system argument rather than a resource.
 pub trait Resource: Component {}
+pub use bhecs_macros::Resource;
 pub struct ResourceComponent;
-impl<T: Component> Resource for T {}
 
 pub struct ResourceParamState<Q: hecs::Query + 'static> {
-    query: PreparedQuery<Q>,
+    // dropped before query, see ViewState.
     borrow: Option<Borrow<Q>>,
+    query: PreparedQuery<Q>,
 }
 struct Borrow<Q: hecs::Query + 'static> {
     iter: PreparedQueryIter<'static, Q>,
@@ -29,14 +35,13 @@ impl<Q: hecs::Query> ResourceParamState<Q> {
         // transmut changes lifetimes. So ensure lifetime gurantees manually.
         // resource is borrowed for world. So no dangling pointer.
         // lock by hecs on this column, will be gone when query_borrow drops.
-        // self.unlock() will drop it. When called from system.apply.
+        // self.unlock() will drop it, see SystemParam::unlock.
         let iter = unsafe { transmute::<hecs::PreparedQueryIter<'_, Q>, hecs::PreparedQueryIter<'_, Q>>(iter) };
         let query_borrow = unsafe { transmute::<hecs::PreparedQueryBorrow<'_, Q>, hecs::PreparedQueryBorrow<'_, Q>>(query_borrow) };
         let borrow = Borrow::<Q> { iter, query_borrow };
         self.borrow = Some(borrow);
         let rv =  self.borrow.as_mut().unwrap().iter.next().map(|v| v.1);
         // # Safety. Borrow is actually from world too. It won't live past function call.
-        // unlock called by system.apply will remove hecs lock.
         return unsafe { transmute::<std::option::Option<<Q as hecs::Query>::Item<'_>>, std::option::Option<<Q as hecs::Query>::Item<'_>>>(rv) };
     }
     pub fn unlock(&mut self) {
@@ -48,7 +53,8 @@ impl<R: Resource> SystemParam for &R {
 
     type Item<'world, 'state> = &'world R;
 
-    fn init_state(_: &mut crate::World, _: &mut crate::system::systemmeta::SystemMeta) -> Self::State {
+    fn init_state(_: &mut crate::World, system_meta: &mut crate::system::systemmeta::SystemMeta) -> Self::State {
+        system_meta.access.add_read(core::any::TypeId::of::<R>());
         Default::default()
     }
 
@@ -62,7 +68,7 @@ impl<R: Resource> SystemParam for &R {
         return a.expect("Resource doesn't exist");
     }
 
-    fn apply(state: &mut Self::State, _: &crate::system::systemmeta::SystemMeta, _: &mut crate::World) {
+    fn unlock(state: &mut Self::State) {
         state.unlock();
     }
 }
@@ -71,7 +77,8 @@ impl<R: Resource> SystemParam for &mut R {
 
     type Item<'world, 'state> = &'world mut R;
 
-    fn init_state(_: &mut crate::World, _: &mut crate::system::systemmeta::SystemMeta) -> Self::State {
+    fn init_state(_: &mut crate::World, system_meta: &mut crate::system::systemmeta::SystemMeta) -> Self::State {
+        system_meta.access.add_write(core::any::TypeId::of::<R>());
         Default::default()
     }
 
@@ -85,7 +92,7 @@ impl<R: Resource> SystemParam for &mut R {
         return a.unwrap();
     }
 
-    fn apply(state: &mut Self::State, _: &crate::system::systemmeta::SystemMeta, _: &mut crate::World) {
+    fn unlock(state: &mut Self::State) {
         state.unlock();
     }
 }
@@ -97,6 +104,7 @@ mod tests {
 
     use super::*;
 
+    #[derive(Resource)]
     struct R1(u8);
 
     #[test]
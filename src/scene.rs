@@ -0,0 +1,313 @@
+//! Scenes: entities described by their registered components and their hierarchy,
+//! spawned into a world as often as needed, e.g. for prefabs. Enabled by the `serde` feature.
+//!
+//! A scene reads from and writes to [RON](ron), so designers can write them by hand:
+//!
+//! ```text
+//! (
+//!     entities: [
+//!         (id: 1, components: {"Name": Name("door"), "Opens": Opens(Entity(3))}, children: [2]),
+//!         (id: 2, components: {"Name": Name("handle"), "Health": Health(3)}),
+//!         (id: 3, components: {"Name": Name("gate")}),
+//!     ],
+//! )
+//! ```
+//!
+//! Components refer to other entities of the scene by writing `Entity(id)` where an [`Entity`] goes.
+mod references;
+
+use core::{any::TypeId, fmt, str::FromStr};
+use std::collections::BTreeMap;
+
+use bevy_utils::{HashMap, HashSet};
+use hecs::Entity;
+use bevy_utils::tracing::warn;
+use ron::{error::SpannedError, ser::PrettyConfig, value::RawValue};
+use serde::{de::Error, Deserialize, Serialize};
+
+use crate::{
+    entitymap::EntityMap,
+    hierarchy::{Children, Parent},
+    typeregistry::TypeRegistry,
+    World,
+};
+use references::WithIds;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub entities: Vec<SceneEntity>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneEntity {
+    /// Identifies the entity within the scene. Components refer to other scene entities by
+    /// writing `Entity(id)` where an [`Entity`] goes.
+    /// Saved scenes use the bits of the saved entities, which their components hold as plain numbers.
+    pub id: u64,
+    /// RON of registered components, by registered name. Only read once the scene is spawned,
+    /// as their types are known from the registry.
+    #[serde(default)]
+    pub components: BTreeMap<String, Box<RawValue>>,
+    /// Ids of the children, in order. [`Parent`] and [`Children`] are never in `components`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<u64>,
+}
+
+impl fmt::Display for Scene {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = ron::ser::to_string_pretty(self, PrettyConfig::default()).map_err(|_| fmt::Error)?;
+        f.write_str(&text)
+    }
+}
+impl FromStr for Scene {
+    type Err = SpannedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ron::from_str(s)
+    }
+}
+
+fn registry(world: &World) -> Result<hecs::Ref<'_, TypeRegistry>, ron::Error> {
+    world.hworld.get::<&TypeRegistry>(world.resource_entity())
+        .map_err(|_| ron::Error::custom("world has no TypeRegistry resource"))
+}
+
+impl World {
+    /// Spawns a copy of every entity of `scene`, returning them in scene order.
+    /// Entity references in registered [`with_map_entities`](crate::typeregistry::RegisterType::with_map_entities)
+    /// components are remapped to the spawned entities. Hooks and observers run as for [`spawn`](Self::spawn).
+    ///
+    /// # Errors
+    /// If a component isn't registered with serde in the world's [`TypeRegistry`], its value
+    /// doesn't match its type, a child id isn't in the scene, or the children form a cycle. Nothing is spawned then.
+    pub fn spawn_scene(&mut self, scene: &Scene) -> Result<Vec<Entity>, ron::Error> {
+        let mut scene_ids = HashSet::with_capacity(scene.entities.len());
+        for entity in &scene.entities {
+            if !scene_ids.insert(entity.id) {
+                return Err(ron::Error::custom(format!("id {} is used twice", entity.id)));
+            }
+        }
+        let mut parents = HashMap::new();
+        for entity in &scene.entities {
+            for &child in &entity.children {
+                if !scene_ids.contains(&child) {
+                    return Err(ron::Error::custom(format!("child {child} of {} isn't in the scene", entity.id)));
+                }
+                if parents.insert(child, entity.id).is_some() {
+                    return Err(ron::Error::custom(format!("{child} is the child of two entities")));
+                }
+            }
+        }
+        for &child in parents.keys() {
+            let mut ancestor = parents.get(&child);
+            while let Some(&id) = ancestor {
+                if id == child {
+                    return Err(ron::Error::custom(format!("{child} is its own ancestor")));
+                }
+                ancestor = parents.get(&id);
+            }
+        }
+        // ids which are valid entity bits stand for themselves, like in saved scenes.
+        // Others, like hand-written ones, stand for placeholders no entity of the scene can have.
+        let mut keys = HashMap::with_capacity(scene.entities.len());
+        let mut ids = HashMap::with_capacity(scene.entities.len());
+        let mut map = EntityMap::default();
+        for &id in &scene_ids {
+            let key = Entity::from_bits(id).unwrap_or_else(|| {
+                Entity::from_bits(u64::from(u32::MAX) << 32 | id).expect("generation isn't zero")
+            });
+            if map.get(key).is_some() {
+                return Err(ron::Error::custom(format!("id {id} clashes with {}", key.to_bits())));
+            }
+            let spawned = self.hworld.reserve_entity();
+            map.insert(key, spawned);
+            keys.insert(id, key);
+            ids.insert(id, spawned);
+        }
+
+        // built in a staging world first, so a bad scene spawns nothing, and references can be
+        // remapped before hooks see the components.
+        let mut staging = hecs::World::new();
+        let mut staged = Vec::with_capacity(scene.entities.len());
+        let map_fns: Vec<_> = {
+            let registry = registry(self)?;
+            for entity in &scene.entities {
+                let mut builder = hecs::EntityBuilder::new();
+                for (name, value) in &entity.components {
+                    let serde = registry.get_by_name(name).and_then(|registration| registration.serde)
+                        .ok_or_else(|| ron::Error::custom(format!("{name} isn't registered with serde")))?;
+                    let mut deserializer = ron::Deserializer::from_str(value.get_ron()).map_err(|error| error.code)?;
+                    let deserializer = WithIds { inner: &mut deserializer, ids: &keys };
+                    (serde.deserialize_erased)(&mut <dyn erased_serde::Deserializer>::erase(deserializer), &mut builder)
+                        .map_err(|error| ron::Error::custom(format!("{name}: {error}")))?;
+                }
+                staged.push(staging.spawn(builder.build()));
+            }
+            registry.iter().filter_map(|registration| registration.map_entities).collect()
+        };
+        for &entity in &staged {
+            for map_entities in &map_fns {
+                map_entities(&staging, entity, &map);
+            }
+        }
+        let spawned: Vec<Entity> = scene.entities.iter().map(|entity| ids[&entity.id]).collect();
+        for (&staged, &entity) in staged.iter().zip(&spawned) {
+            let components = staging.take(staged).expect("staged entity");
+            self.spawn_at(entity, components);
+        }
+        for entity in &scene.entities {
+            for child in &entity.children {
+                // checked above, so this only fails if a hook or observer despawned one of them.
+                if let Err(error) = self.set_parent(ids[child], ids[&entity.id]) {
+                    warn!("could not make {} the parent of {child} in the scene: {error:?}", entity.id);
+                }
+            }
+        }
+        Ok(spawned)
+    }
+
+    /// Saves `entities`, with their registered serde components, into a scene. Only the
+    /// children among `entities` are kept, so pass descendants too to save a whole hierarchy.
+    ///
+    /// # Errors
+    /// If the world has no [`TypeRegistry`], or a component fails to serialize.
+    pub fn save_scene(&self, entities: impl IntoIterator<Item = Entity>) -> Result<Scene, ron::Error> {
+        let registry = registry(self)?;
+        let entities: Vec<Entity> = entities.into_iter().filter(|&entity| self.hworld.contains(entity)).collect();
+        let hierarchy = [TypeId::of::<Parent>(), TypeId::of::<Children>()];
+        let mut scene = Scene::default();
+        for &entity in &entities {
+            let entity_ref = self.hworld.entity(entity).expect("checked above");
+            let mut saved = SceneEntity { id: entity.to_bits().get(), ..SceneEntity::default() };
+            for type_id in entity_ref.component_types().filter(|type_id| !hierarchy.contains(type_id)) {
+                let Some(registration) = registry.get(type_id) else { continue };
+                let Some(serde) = registration.serde else { continue };
+                let mut value = None;
+                (serde.with_component)(&entity_ref, &mut |component| value = Some(RawValue::from_rust(&component)));
+                saved.components.insert(registration.name().to_owned(), value.expect("with_component calls back once")?);
+            }
+            if let Some(children) = entity_ref.get::<&Children>() {
+                saved.children = children.iter().filter(|child| entities.contains(child))
+                    .map(|child| child.to_bits().get()).collect();
+            }
+            scene.entities.push(saved);
+        }
+        Ok(scene)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Name(String);
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Health(u32);
+    /// A plain number, which mustn't be mistaken for an entity.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Gold(u64);
+    /// Points at another entity, remapped when spawned.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Target(Entity);
+    impl crate::entitymap::MapEntities for Target {
+        fn map_entities(&mut self, map: &EntityMap) { self.0 = map.map(self.0); }
+    }
+
+    #[test]
+    fn handwritten() {
+        let scene: Scene = "
+            (
+                entities: [
+                    (id: 1, components: {\"Name\": Name(\"door\")}, children: [2, 3]),
+                    (id: 2, components: {\"Name\": Name(\"handle\"), \"Health\": Health(3)}),
+                    (id: 3),
+                ],
+            )
+        ".parse().unwrap();
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register_named::<Name>("Name").with_serde();
+        let _ = registry.register_named::<Health>("Health").with_serde();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        let first = w.spawn_scene(&scene).unwrap();
+        let second = w.spawn_scene(&scene).unwrap();
+        assert_ne!(first, second);
+        let [door, handle, empty] = first[..] else { panic!() };
+        assert_eq!(Name("door".into()), *w.get::<&Name>(door).unwrap());
+        assert_eq!(Health(3), *w.get::<&Health>(handle).unwrap());
+        assert_eq!([handle, empty], w.get::<&Children>(door).unwrap()[..]);
+        assert_eq!(door, w.get::<&Parent>(handle).unwrap().get());
+    }
+
+    #[test]
+    fn handwritten_references() {
+        let scene: Scene = "
+            (
+                entities: [
+                    (id: 1, components: {\"Target\": Target(Entity(2)), \"Gold\": Gold(2)}),
+                    (id: 2, components: {\"Target\": Target(Entity(1)), \"Pair\": (Entity(2), 1)}),
+                ],
+            )
+        ".parse().unwrap();
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register_named::<Target>("Target").with_serde().with_map_entities();
+        let _ = registry.register_named::<Gold>("Gold").with_serde();
+        let _ = registry.register_named::<(Entity, u64)>("Pair").with_serde();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        let spawned = w.spawn_scene(&scene).unwrap();
+        let [a, b] = spawned[..] else { panic!() };
+        assert_eq!(b, w.get::<&Target>(a).unwrap().0);
+        assert_eq!(Gold(2), *w.get::<&Gold>(a).unwrap());
+        assert_eq!(a, w.get::<&Target>(b).unwrap().0);
+        // Pair has no map_entities, so its reference isn't remapped, but the plain number is left alone.
+        let pair = *w.get::<&(Entity, u64)>(b).unwrap();
+        assert_eq!(1, pair.1);
+        assert_ne!(b, pair.0);
+    }
+
+    #[test]
+    fn save_and_respawn() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register_named::<Name>("Name").with_serde();
+        let _ = registry.register_named::<Target>("Target").with_serde().with_map_entities();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        let guard = w.spawn((Name("guard".into()),));
+        let sword = w.spawn((Name("sword".into()), Target(guard)));
+        w.add_child(guard, sword).unwrap();
+        let outside = w.spawn((Name("outside".into()),));
+        w.add_child(guard, outside).unwrap();
+
+        let text = w.save_scene([guard, sword]).unwrap().to_string();
+        let scene: Scene = text.parse().unwrap();
+        assert_eq!(2, scene.entities.len());
+        let copies = w.spawn_scene(&scene).unwrap();
+        let [guard2, sword2] = copies[..] else { panic!() };
+        assert_eq!(guard2, w.get::<&Target>(sword2).unwrap().0);
+        // the child left out of the scene isn't copied.
+        assert_eq!([sword2], w.get::<&Children>(guard2).unwrap()[..]);
+    }
+
+    #[test]
+    fn bad_scene_spawns_nothing() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register_named::<Target>("Target").with_serde().with_map_entities();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        let before = w.len();
+        let unknown: Scene = "(entities: [(id: 1, components: {\"Mana\": 3})])".parse().unwrap();
+        assert!(w.spawn_scene(&unknown).is_err());
+        let orphan: Scene = "(entities: [(id: 1, children: [5])])".parse().unwrap();
+        assert!(w.spawn_scene(&orphan).is_err());
+        let cycle: Scene = "(entities: [(id: 1, children: [2]), (id: 2, children: [1])])".parse().unwrap();
+        assert!(w.spawn_scene(&cycle).is_err());
+        let bad_reference: Scene = "(entities: [(id: 1, components: {\"Target\": Target(Entity(7))})])".parse().unwrap();
+        assert!(w.spawn_scene(&bad_reference).is_err());
+        // a plain number is never read as a scene id.
+        let plain_id: Scene = "(entities: [(id: 1, components: {\"Target\": Target(1)})])".parse().unwrap();
+        assert!(w.spawn_scene(&plain_id).is_err());
+        assert_eq!(before, w.len());
+    }
+}
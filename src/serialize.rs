@@ -0,0 +1,341 @@
+//! Saving and loading a [`World`] with serde, enabled by the `serde` feature.
+//!
+//! Only component and resource types registered in the world's [`TypeRegistry`] with
+//! [`RegisterType::with_serde`] are saved, under their registered names. Everything else,
+//! like systems and observers, is left out. Components are (de)serialized as their own types,
+//! so any serde format works, including ones which aren't self-describing, like bincode.
+//! [Scenes](crate::scene) are written in [RON](ron).
+use core::fmt;
+
+use hecs::{serialize::row::{self, DeserializeContext, SerializeContext}, Component, Entity, EntityBuilder, EntityRef};
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{entitymap::EntityMap, typeregistry::{RegisterType, TypeRegistry}, World};
+
+/// Hands the component of an entity, which must have it, to the callback.
+type WithComponentFn = fn(&EntityRef<'_>, &mut dyn FnMut(&dyn erased_serde::Serialize));
+/// Deserializes a component from any format and adds it to the builder.
+type DeserializeErasedFn = fn(&mut dyn erased_serde::Deserializer<'_>, &mut EntityBuilder) -> Result<(), erased_serde::Error>;
+
+/// Type-erased serde functions of a registered type.
+#[derive(Clone, Copy)]
+pub(crate) struct SerdeFns {
+    /// Hands the component of an entity, which must have it, to the callback, to be serialized in any format.
+    pub(crate) with_component: WithComponentFn,
+    /// Deserializes the component from any format, typed, and adds it to the builder.
+    pub(crate) deserialize_erased: DeserializeErasedFn,
+}
+
+impl<T: Component + Serialize + DeserializeOwned> RegisterType<'_, T> {
+    /// Lets `T` be saved and loaded along with the world.
+    #[must_use]
+    pub fn with_serde(self) -> Self {
+        self.registration.serde = Some(SerdeFns {
+            with_component: |entity, f| f(&*entity.get::<&T>().expect("entity has the component")),
+            deserialize_erased: |deserializer, builder| {
+                builder.add(erased_serde::deserialize::<T>(deserializer)?);
+                Ok(())
+            },
+        });
+        self
+    }
+}
+
+impl World {
+    /// Saves every entity, with its registered components, and the registered resources.
+    /// Observers and registered systems are left out.
+    ///
+    /// # Errors
+    /// If the world has no [`TypeRegistry`], or the serializer fails.
+    pub fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let registry = self.hworld.get::<&TypeRegistry>(self.resource_entity())
+            .map_err(|_| ser::Error::custom("world has no TypeRegistry resource"))?;
+        let mut saved = serializer.serialize_struct("World", 2)?;
+        saved.serialize_field("resource_entity", &self.resource_entity())?;
+        saved.serialize_field("entities", &Entities { world: self, registry: &registry })?;
+        saved.end()
+    }
+
+    /// Loads a world saved by [`serialize`](Self::serialize) into a new world, which gets a copy of `registry`.
+    /// Entities get new ids, returned as a map from the saved ids, and the registered
+    /// [`with_map_entities`](RegisterType::with_map_entities) types are updated to them.
+    ///
+    /// No hooks or observers run, the saved components are assumed consistent with each other.
+    ///
+    /// # Errors
+    /// If the data is malformed or names a type without registered serde.
+    pub fn deserialize<'de, D: Deserializer<'de>>(registry: &TypeRegistry, deserializer: D)
+        -> Result<(World, EntityMap), D::Error> {
+        let (saved_resource_entity, mut saved) = deserializer.deserialize_struct(
+            "World", &["resource_entity", "entities"], WorldVisitor { registry },
+        )?;
+        let mut world = World::new();
+        world.insert_resource(registry.clone());
+        let ids: Vec<Entity> = saved.iter().map(|entity| entity.entity()).collect();
+        let mut map = EntityMap::default();
+        for &id in &ids {
+            let new = if id == saved_resource_entity { world.resource_entity() } else { world.hworld.spawn(()) };
+            map.insert(id, new);
+        }
+        for id in ids {
+            let components = saved.take(id).expect("entity was just listed");
+            world.hworld.insert(map.map(id), components).expect("entity was just spawned");
+        }
+        world.map_entities(registry, &map);
+        Ok((world, map))
+    }
+
+    /// Updates the entity references of the mapped entities' registered components.
+    pub(crate) fn map_entities(&mut self, registry: &TypeRegistry, map: &EntityMap) {
+        let fns: Vec<_> = registry.iter().filter_map(|registration| registration.map_entities).collect();
+        for (_, entity) in map.iter() {
+            for map_entities in &fns {
+                map_entities(&self.hworld, entity, map);
+            }
+        }
+    }
+}
+
+/// The entities of a world but observers and registered systems, in the layout of hecs' row serialization.
+struct Entities<'a> {
+    world: &'a World,
+    registry: &'a TypeRegistry,
+}
+impl Serialize for Entities<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entities: Vec<EntityRef<'_>> = self.world.hworld.iter()
+            .filter(|entity| !self.world.holds_system(entity.entity()))
+            .collect();
+        let mut map = serializer.serialize_map(Some(entities.len()))?;
+        for entity in entities {
+            map.serialize_entry(&entity.entity(), &Components { entity, registry: self.registry })?;
+        }
+        map.end()
+    }
+}
+
+/// The registered components of one entity, by registered name.
+struct Components<'a> {
+    entity: EntityRef<'a>,
+    registry: &'a TypeRegistry,
+}
+impl Serialize for Components<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut context = Context { registry: self.registry };
+        let map = serializer.serialize_map(context.component_count(self.entity))?;
+        context.serialize_entity(self.entity, map)
+    }
+}
+
+/// Serializes components by registered name and deserializes them back, through the registry.
+struct Context<'a> {
+    registry: &'a TypeRegistry,
+}
+impl SerializeContext for Context<'_> {
+    fn serialize_entity<S: SerializeMap>(&mut self, entity: EntityRef<'_>, mut map: S) -> Result<S::Ok, S::Error> {
+        for type_id in entity.component_types() {
+            let Some(registration) = self.registry.get(type_id) else { continue };
+            let Some(serde) = registration.serde else { continue };
+            map.serialize_entry(registration.name(), &ComponentRef { entity: &entity, serde })?;
+        }
+        map.end()
+    }
+
+    fn component_count(&self, entity: EntityRef<'_>) -> Option<usize> {
+        let saved = entity.component_types()
+            .filter(|&type_id| self.registry.get(type_id).is_some_and(|r| r.serde.is_some()))
+            .count();
+        Some(saved)
+    }
+}
+impl DeserializeContext for Context<'_> {
+    fn deserialize_entity<'de, M: MapAccess<'de>>(&mut self, mut map: M, entity: &mut EntityBuilder) -> Result<(), M::Error> {
+        while let Some(name) = map.next_key::<String>()? {
+            let serde = self.registry.get_by_name(&name).and_then(|registration| registration.serde)
+                .ok_or_else(|| de::Error::custom(format!("{name} isn't registered with serde")))?;
+            map.next_value_seed(ComponentSeed { serde, builder: entity })?;
+        }
+        Ok(())
+    }
+}
+
+/// One registered component of an entity, serialized as its own type.
+struct ComponentRef<'a, 'e> {
+    entity: &'a EntityRef<'e>,
+    serde: SerdeFns,
+}
+impl Serialize for ComponentRef<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut serializer = Some(serializer);
+        let mut result = None;
+        (self.serde.with_component)(self.entity, &mut |component| {
+            result = serializer.take().map(|serializer| erased_serde::serialize(component, serializer));
+        });
+        result.expect("with_component calls back once")
+    }
+}
+
+/// Deserializes one component as its own type, so formats which aren't self-describing work too.
+struct ComponentSeed<'a> {
+    serde: SerdeFns,
+    builder: &'a mut EntityBuilder,
+}
+impl<'de> DeserializeSeed<'de> for ComponentSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.serde.deserialize_erased)(&mut deserializer, self.builder).map_err(de::Error::custom)
+    }
+}
+
+struct EntitiesSeed<'a> {
+    registry: &'a TypeRegistry,
+}
+impl<'de> DeserializeSeed<'de> for EntitiesSeed<'_> {
+    type Value = hecs::World;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        row::deserialize(&mut Context { registry: self.registry }, deserializer)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum Field { ResourceEntity, Entities }
+
+struct WorldVisitor<'a> {
+    registry: &'a TypeRegistry,
+}
+impl<'de> Visitor<'de> for WorldVisitor<'_> {
+    type Value = (Entity, hecs::World);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a saved world")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let resource_entity = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let entities = seq.next_element_seed(EntitiesSeed { registry: self.registry })?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok((resource_entity, entities))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut resource_entity = None;
+        let mut entities = None;
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::ResourceEntity => resource_entity = Some(map.next_value()?),
+                Field::Entities => entities = Some(map.next_value_seed(EntitiesSeed { registry: self.registry })?),
+            }
+        }
+        let resource_entity = resource_entity.ok_or_else(|| de::Error::missing_field("resource_entity"))?;
+        let entities = entities.ok_or_else(|| de::Error::missing_field("entities"))?;
+        Ok((resource_entity, entities))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{hierarchy::{Children, Parent}, resource::Resource};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Health(u32);
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Name(String);
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Resource)]
+    struct Score { red: u32, blue: u32 }
+    /// Not registered, so not saved.
+    struct Secret;
+
+    fn registry() -> TypeRegistry {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register_named::<Health>("Health").with_serde();
+        let _ = registry.register_named::<Name>("Name").with_serde();
+        let _ = registry.register_named::<Score>("Score").with_serde();
+        let _ = registry.register_named::<Parent>("Parent").with_serde().with_map_entities();
+        let _ = registry.register_named::<Children>("Children").with_serde().with_map_entities();
+        registry
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut w = World::new();
+        w.insert_resource(registry());
+        w.insert_resource(Score { red: 1, blue: 2 });
+        // spawned first, so the loaded world has to give the others new ids.
+        let _gap = w.spawn((Secret,));
+        let parent = w.spawn((Name("parent".into()), Health(10)));
+        let child = w.spawn((Health(3), Secret));
+        w.add_child(parent, child).unwrap();
+
+        let saved = ron::to_string(&SaveWorld(&w)).unwrap();
+        let (mut loaded, map) = World::deserialize(&registry(), &mut ron::Deserializer::from_str(&saved).unwrap()).unwrap();
+
+        assert_eq!(Score { red: 1, blue: 2 }, *loaded.get_resource::<Score>());
+        let (parent, child) = (map.get(parent).unwrap(), map.get(child).unwrap());
+        assert_eq!(Name("parent".into()), *loaded.get::<&Name>(parent).unwrap());
+        assert_eq!(Health(3), *loaded.get::<&Health>(child).unwrap());
+        assert!(loaded.get::<&Secret>(child).is_err());
+        assert_eq!(parent, loaded.get::<&Parent>(child).unwrap().get());
+        assert_eq!([child], loaded.get::<&Children>(parent).unwrap()[..]);
+    }
+
+    #[test]
+    fn bincode_round_trip() {
+        let mut w = World::new();
+        w.insert_resource(registry());
+        w.insert_resource(Score { red: 3, blue: 4 });
+        let parent = w.spawn((Name("parent".into()), Health(10)));
+        let child = w.spawn((Health(3),));
+        w.add_child(parent, child).unwrap();
+
+        let bytes = bincode::Options::serialize(bincode::options(), &SaveWorld(&w)).unwrap();
+        let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+        let (mut loaded, map) = World::deserialize(&registry(), &mut deserializer).unwrap();
+
+        assert_eq!(Score { red: 3, blue: 4 }, *loaded.get_resource::<Score>());
+        let (parent, child) = (map.get(parent).unwrap(), map.get(child).unwrap());
+        assert_eq!(Name("parent".into()), *loaded.get::<&Name>(parent).unwrap());
+        assert_eq!(Health(3), *loaded.get::<&Health>(child).unwrap());
+        assert_eq!([child], loaded.get::<&Children>(parent).unwrap()[..]);
+    }
+
+    #[test]
+    fn unknown_type() {
+        let mut w = World::new();
+        w.insert_resource(registry());
+        w.spawn((Health(1),));
+        let saved = ron::to_string(&SaveWorld(&w)).unwrap();
+        assert!(World::deserialize(&TypeRegistry::default(), &mut ron::Deserializer::from_str(&saved).unwrap()).is_err());
+    }
+
+    #[test]
+    fn systems_left_out() {
+        let mut w = World::new();
+        w.insert_resource(registry());
+        w.insert_resource(Score { red: 0, blue: 0 });
+        let _ = w.register_system(|score: &mut Score| score.red += 1);
+        w.spawn((Health(1),));
+        w.spawn((Secret,));
+
+        let saved = ron::to_string(&SaveWorld(&w)).unwrap();
+        let (loaded, _) = World::deserialize(&registry(), &mut ron::Deserializer::from_str(&saved).unwrap()).unwrap();
+        // the resource entity and the two spawned ones.
+        assert_eq!(3, loaded.len());
+    }
+
+    struct SaveWorld<'a>(&'a World);
+    impl Serialize for SaveWorld<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize(serializer)
+        }
+    }
+}
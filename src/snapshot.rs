@@ -0,0 +1,406 @@
+//! Copies of a world's state to go back to later, e.g. for rollback networking.
+//!
+//! Only components and resources registered with
+//! [`RegisterType::with_clone`](crate::typeregistry::RegisterType::with_clone) are captured, along with
+//! [`Parent`] and [`Children`] so the hierarchy comes back too. Other components are left as they are
+//! by [`World::restore`], and so are systems and observers, but for their change ticks.
+use core::any::TypeId;
+use std::collections::VecDeque;
+
+use bevy_utils::{HashMap, HashSet, TypeIdMap};
+use hecs::{BuiltEntityClone, DynamicBundle, Entity, EntityBuilderClone, TakenEntity, TypeInfo};
+
+use crate::{
+    hierarchy::{Children, Parent},
+    typeregistry::{CloneFn, TypeRegistry},
+    ChangeTick, World,
+};
+
+/// The Clone-registered components of every entity, by id, and the change tick.
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    change_tick: ChangeTick,
+    last_check_tick: ChangeTick,
+    /// The types captured, which may not be the ones registered when restoring.
+    captured: HashSet<TypeId>,
+    entities: Vec<(Entity, BuiltEntityClone)>,
+}
+
+/// Error from [`World::restore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreError {
+    /// The id of this saved entity was reused since by an observer or registered system,
+    /// which restoring would despawn. Nothing is restored.
+    HeldBySystem(Entity),
+}
+
+impl WorldSnapshot {
+    #[must_use]
+    pub fn change_tick(&self) -> ChangeTick { self.change_tick }
+    /// Ids of the entities alive when the snapshot was taken.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter().map(|(entity, _)| *entity)
+    }
+}
+
+/// The clone functions of the captured types: the Clone-registered ones, and the hierarchy.
+fn clone_fns(world: &World) -> TypeIdMap<CloneFn> {
+    let mut clone_fns: TypeIdMap<CloneFn> = match world.hworld.get::<&TypeRegistry>(world.resource_entity()) {
+        Ok(registry) => registry.iter()
+            .filter_map(|registration| Some((registration.type_id(), registration.clone?)))
+            .collect(),
+        Err(_) => TypeIdMap::default(),
+    };
+    clone_fns.insert(TypeId::of::<Parent>(), |entity, builder| {
+        if let Some(parent) = entity.get::<&Parent>() {
+            builder.add(*parent);
+        }
+    });
+    clone_fns.insert(TypeId::of::<Children>(), |entity, builder| {
+        if let Some(children) = entity.get::<&Children>() {
+            builder.add(Children::clone(&children));
+        }
+    });
+    clone_fns
+}
+
+impl World {
+    /// Captures every entity but observers and registered systems.
+    #[must_use]
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let clone_fns = clone_fns(self);
+        let entities = self.hworld.iter()
+            .filter(|entity| !self.holds_system(entity.entity()))
+            .map(|entity| {
+                let mut builder = EntityBuilderClone::new();
+                for type_id in entity.component_types() {
+                    if let Some(clone) = clone_fns.get(&type_id) {
+                        clone(&entity, &mut builder);
+                    }
+                }
+                (entity.entity(), builder.build())
+            })
+            .collect();
+        WorldSnapshot {
+            change_tick: self.change_tick,
+            last_check_tick: self.last_check_tick,
+            captured: clone_fns.into_keys().collect(),
+            entities,
+        }
+    }
+
+    /// Puts the world back as it was when `snapshot` was taken: entities spawned since are despawned,
+    /// despawned ones respawned with the same ids, and the captured components and resources
+    /// replaced by the saved ones. The change tick goes back too.
+    /// Observers and registered systems are left alone, even those added since, but those which ran
+    /// since are clamped back behind the change tick, so they see every change as new once.
+    ///
+    /// Like loading a saved world, no hooks or observers run and no removals are recorded.
+    ///
+    /// # Errors
+    /// If an observer or registered system took the id of a saved entity. The world is left as it is then.
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) -> Result<(), RestoreError> {
+        let systems: HashMap<u32, Entity> = self.hworld.iter().map(|entity| entity.entity())
+            .filter(|&entity| self.holds_system(entity))
+            .map(|entity| (entity.id(), entity))
+            .collect();
+        let taken = snapshot.entities()
+            .find(|entity| systems.get(&entity.id()).is_some_and(|system| system != entity));
+        if let Some(entity) = taken {
+            return Err(RestoreError::HeldBySystem(entity));
+        }
+        let captured = &snapshot.captured;
+        let saved: HashSet<Entity> = snapshot.entities().collect();
+        let spawned: Vec<Entity> = self.hworld.iter().map(|entity| entity.entity())
+            .filter(|&entity| !saved.contains(&entity) && !self.holds_system(entity))
+            .collect();
+        for entity in spawned {
+            let _ = self.hworld.despawn(entity);
+        }
+        // every entity is taken out once and rebuilt from its other components and the saved ones.
+        let mut staging = hecs::World::new();
+        for (entity, components) in &snapshot.entities {
+            let Ok(current) = self.hworld.entity(*entity) else {
+                self.hworld.spawn_at(*entity, components);
+                continue;
+            };
+            let unchanged = components.with_ids(<[TypeId]>::is_empty)
+                && !current.component_types().any(|type_id| captured.contains(&type_id));
+            if unchanged {
+                continue;
+            }
+            let staged = staging.spawn(self.hworld.take(*entity).expect("entity exists"));
+            let kept = staging.take(staged).expect("just spawned");
+            self.hworld.spawn_at(*entity, Restored::new(kept, captured, components));
+        }
+        self.change_tick = snapshot.change_tick;
+        self.last_check_tick = snapshot.last_check_tick;
+        self.clamp_ticks(snapshot.change_tick);
+        Ok(())
+    }
+}
+
+/// An entity's components which aren't captured, and its saved ones.
+struct Restored<'a> {
+    kept: TakenEntity<'a>,
+    /// Captured types, which `kept` drops instead of handing out.
+    captured: &'a HashSet<TypeId>,
+    saved: &'a BuiltEntityClone,
+    /// The types handed out, sorted like hecs wants them.
+    types: Vec<TypeInfo>,
+    ids: Vec<TypeId>,
+}
+
+impl<'a> Restored<'a> {
+    fn new(kept: TakenEntity<'a>, captured: &'a HashSet<TypeId>, saved: &'a BuiltEntityClone) -> Self {
+        let mut types: Vec<TypeInfo> = kept.type_info().into_iter()
+            .filter(|ty| !captured.contains(&ty.id()))
+            .chain(saved.type_info())
+            .collect();
+        types.sort_unstable();
+        let ids = types.iter().map(TypeInfo::id).collect();
+        Self { kept, captured, saved, types, ids }
+    }
+}
+
+// # Safety
+// `put` hands out exactly the components in `types`: the ones of `kept` which aren't in `captured`, and
+// the saved ones. `captured` is the set the snapshot was taken with, so it has every saved type,
+// and no type is handed out twice. The captured ones of `kept` are dropped instead.
+unsafe impl DynamicBundle for Restored<'_> {
+    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+        f(&self.ids)
+    }
+
+    fn type_info(&self) -> Vec<TypeInfo> {
+        self.types.clone()
+    }
+
+    unsafe fn put(self, mut f: impl FnMut(*mut u8, TypeInfo)) {
+        let captured = self.captured;
+        self.kept.put(|ptr, ty| {
+            if captured.contains(&ty.id()) {
+                ty.drop(ptr);
+            } else {
+                f(ptr, ty);
+            }
+        });
+        self.saved.put(f);
+    }
+}
+
+/// The last few snapshots, dropping the oldest when full.
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<WorldSnapshot>,
+    capacity: usize,
+}
+
+impl SnapshotBuffer {
+    /// # Panics
+    /// If `capacity` is zero.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "a snapshot buffer needs room for one snapshot");
+        Self { snapshots: VecDeque::with_capacity(capacity), capacity }
+    }
+    pub fn push(&mut self, snapshot: WorldSnapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+    /// The snapshot pushed `ago` pushes before the latest one, which is `get(0)`.
+    #[must_use]
+    pub fn get(&self, ago: usize) -> Option<&WorldSnapshot> {
+        let index = self.snapshots.len().checked_sub(ago + 1)?;
+        self.snapshots.get(index)
+    }
+    /// Restores `world` to [`get(ago)`](Self::get), dropping the snapshots newer than it.
+    /// Returns false, doing nothing, if there is no such snapshot.
+    ///
+    /// # Errors
+    /// If [`World::restore`] fails, which keeps the snapshots.
+    pub fn rollback(&mut self, world: &mut World, ago: usize) -> Result<bool, RestoreError> {
+        let Some(snapshot) = self.get(ago) else { return Ok(false) };
+        world.restore(snapshot)?;
+        self.snapshots.truncate(self.snapshots.len() - ago);
+        Ok(true)
+    }
+    #[must_use]
+    pub fn len(&self) -> usize { self.snapshots.len() }
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.snapshots.is_empty() }
+    #[must_use]
+    pub fn capacity(&self) -> usize { self.capacity }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        changetick::CHECK_TICK_THRESHOLD,
+        event::Event,
+        observer::Trigger,
+        resource::Resource,
+        system::systemchangetick::SystemChangeTick,
+    };
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Pos(i32);
+    #[derive(Clone, Debug, PartialEq, Resource)]
+    struct Frame(u32);
+    /// Not registered, so left alone by restore.
+    struct Local(u32);
+    struct Ping;
+    impl Event for Ping {}
+
+    #[test]
+    fn restore_exact_ids() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Pos>().with_clone();
+        let _ = registry.register::<Frame>().with_clone();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        w.insert_resource(Frame(0));
+        let kept = w.spawn((Pos(1), Local(1)));
+        let despawned = w.spawn((Pos(2),));
+        let snapshot = w.snapshot();
+        let tick = w.change_tick();
+
+        w.get::<&mut Pos>(kept).unwrap().0 = 10;
+        w.get::<&mut Local>(kept).unwrap().0 = 10;
+        w.insert_one(kept, Frame(5)).unwrap();
+        w.despawn(despawned).unwrap();
+        let reused = w.spawn((Pos(3),));
+        w.get_resource_mut::<Frame>().0 = 9;
+        w.increment_change_tick();
+
+        w.restore(&snapshot).unwrap();
+        assert_eq!(Pos(1), *w.get::<&Pos>(kept).unwrap());
+        assert_eq!(10, w.get::<&Local>(kept).unwrap().0);
+        assert!(w.get::<&Frame>(kept).is_err(), "added since, so removed");
+        assert_eq!(Pos(2), *w.get::<&Pos>(despawned).unwrap());
+        assert!(!w.contains(reused));
+        assert_eq!(Frame(0), *w.get_resource::<Frame>());
+        assert_eq!(tick, w.change_tick());
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Pos>().with_clone();
+        let _ = registry.register::<Frame>().with_clone();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        w.insert_resource(Frame(0));
+        let mut buffer = SnapshotBuffer::new(3);
+        for frame in 1..=5 {
+            w.get_resource_mut::<Frame>().0 = frame;
+            buffer.push(w.snapshot());
+        }
+        assert_eq!(3, buffer.len());
+        assert!(buffer.get(3).is_none());
+        assert!(buffer.rollback(&mut w, 2).unwrap());
+        assert_eq!(Frame(3), *w.get_resource::<Frame>());
+        assert_eq!(1, buffer.len());
+        assert!(!buffer.rollback(&mut w, 1).unwrap());
+    }
+
+    #[test]
+    fn systems_survive() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Frame>().with_clone();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        w.insert_resource(Frame(0));
+        let snapshot = w.snapshot();
+        w.observe(|_: Trigger<Ping>, frame: &mut Frame| frame.0 += 1);
+        let system = w.register_system(|frame: &mut Frame| frame.0 += 10);
+
+        w.restore(&snapshot).unwrap();
+        w.trigger(Ping);
+        w.run_system(system).unwrap();
+        assert_eq!(Frame(11), *w.get_resource::<Frame>());
+    }
+
+    #[test]
+    fn registry_changed_since() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Pos>().with_clone();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        let e = w.spawn((Pos(1),));
+        let snapshot = w.snapshot();
+        w.insert_resource(TypeRegistry::default());
+        w.get::<&mut Pos>(e).unwrap().0 = 2;
+
+        // restored with the types captured, not the ones registered now.
+        w.restore(&snapshot).unwrap();
+        assert_eq!(Pos(1), *w.get::<&Pos>(e).unwrap());
+    }
+
+    #[test]
+    fn reused_id_refused() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Frame>().with_clone();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        w.insert_resource(Frame(0));
+        let e = w.spawn((Pos(1),));
+        let snapshot = w.snapshot();
+        w.despawn(e).unwrap();
+        let system = w.register_system(|frame: &mut Frame| frame.0 += 1);
+        assert_eq!(e.id(), system.entity().id());
+
+        assert_eq!(Err(RestoreError::HeldBySystem(e)), w.restore(&snapshot));
+        assert!(!w.contains(e));
+        w.run_system(system).unwrap();
+        assert_eq!(Frame(1), *w.get_resource::<Frame>());
+    }
+
+    #[test]
+    fn restore_ticks() {
+        let mut w = World::new();
+        let system = w.register_system(|ticks: SystemChangeTick| ticks);
+        let snapshot = w.snapshot();
+        for _ in 0..3 {
+            w.run_system(system).unwrap();
+        }
+        w.change_tick = w.change_tick.wrapping_add(CHECK_TICK_THRESHOLD);
+        w.check_change_ticks();
+
+        w.restore(&snapshot).unwrap();
+        assert_eq!(snapshot.last_check_tick, w.last_check_tick);
+        // back to where the system last ran, which mustn't hide what changed on the way.
+        let changed = w.increment_change_tick();
+        for _ in 0..2 {
+            w.increment_change_tick();
+        }
+        let ticks = w.run_system(system).unwrap();
+        assert!(ticks.is_changed(changed));
+    }
+
+    #[test]
+    fn restore_hierarchy() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register::<Pos>().with_clone();
+        let mut w = World::new();
+        w.insert_resource(registry);
+        let parent = w.spawn((Pos(0),));
+        let other = w.spawn((Pos(1),));
+        let child = w.spawn((Pos(2),));
+        w.set_parent(child, parent).unwrap();
+        let snapshot = w.snapshot();
+
+        let _ = w.spawn((Pos(3),));
+        let added = w.spawn((Pos(4),));
+        w.set_parent(added, parent).unwrap();
+        w.set_parent(child, other).unwrap();
+
+        w.restore(&snapshot).unwrap();
+        assert_eq!(parent, w.get::<&Parent>(child).unwrap().get());
+        assert_eq!(vec![child], **w.get::<&Children>(parent).unwrap());
+        assert!(w.get::<&Children>(other).is_err());
+    }
+}
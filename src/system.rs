@@ -1,3 +1,4 @@
+pub mod access;
 pub mod query;
 pub mod systemparam;
 pub mod exclusivesystemparam;
@@ -5,9 +6,21 @@ pub mod systemmeta;
 pub mod systeminput;
 pub mod functionsystem;
 pub mod exclusivefunctionsystem;
+pub mod parallelcommands;
+pub mod paramset;
+pub mod systemstate;
+pub mod local;
+pub mod systemname;
+pub mod systemregistry;
+pub mod adaptersystem;
+pub mod deferred;
+pub mod systemchangetick;
+pub mod commands;
+
+use adaptersystem::MapSystem;
 
 use systeminput::{SystemIn, SystemInput};
-use crate::World;
+use crate::{ChangeTick, World};
 pub trait System: Send + Sync + 'static {
     /// The system's input.
     type In: SystemInput;
@@ -25,13 +38,15 @@ pub trait System: Send + Sync + 'static {
     fn run(&mut self, input: SystemIn<'_, Self>, world: &mut World)
         -> Self::Out {
         if !self.is_initialized(world) { self.initialize(world); }
+        world.check_change_ticks();
+        self.check_change_tick(world.change_tick());
         let rv = self.run_unchecked(input, world);
         self.apply_deferred(world);
         return rv;
     }
 
 
-    /// Applies any [`Deferred`](crate::system::Deferred) system parameters (or other system buffers) of this system to the world.
+    /// Applies any [`Deferred`](crate::system::deferred::Deferred) system parameters (or other system buffers) of this system to the world.
     ///
     /// This is where [`Commands`](crate::system::Commands) get applied.
     fn apply_deferred(&mut self, world: &mut World);
@@ -41,6 +56,10 @@ pub trait System: Send + Sync + 'static {
 
     /// Initialize the system.
     fn is_initialized(&mut self, _world: &mut World) -> bool;
+
+    /// Clamps the ticks stored by this system, so they stay comparable after `change_tick` wraps.
+    /// See [`World::check_change_ticks`].
+    fn check_change_tick(&mut self, change_tick: ChangeTick);
 }
 pub trait IntoSystem<In: SystemInput, Out, Marker>: Sized {
     /// The type of [`System`] that this instance converts into.
@@ -48,6 +67,58 @@ pub trait IntoSystem<In: SystemInput, Out, Marker>: Sized {
 
     /// Turns this value into its corresponding [`System`].
     fn into_system(this: Self) -> Self::System;
+
+    /// Passes the output of this system through `f`.
+    fn map<T, F>(self, f: F) -> MapSystem<Self::System, F>
+    where
+        F: FnMut(Out) -> T + Send + Sync + 'static,
+    {
+        MapSystem::new(IntoSystem::into_system(self), f)
+    }
+
+    /// Drops the output of this system.
+    fn ignore(self) -> MapSystem<Self::System, fn(Out)>
+    where
+        Out: 'static,
+    {
+        self.map(drop as fn(Out))
+    }
+}
+
+/// A type-erased system, for storing systems of different types together.
+pub type BoxedSystem<In = (), Out = ()> = Box<dyn System<In = In, Out = Out>>;
+
+impl<In: SystemInput + 'static, Out: 'static> System for BoxedSystem<In, Out> {
+    type In = In;
+    type Out = Out;
+
+    fn is_exclusive(&self) -> bool {
+        (**self).is_exclusive()
+    }
+
+    fn has_deferred(&self) -> bool {
+        (**self).has_deferred()
+    }
+
+    fn run_unchecked(&mut self, input: SystemIn<'_, Self>, world: &mut World) -> Self::Out {
+        (**self).run_unchecked(input, world)
+    }
+
+    fn apply_deferred(&mut self, world: &mut World) {
+        (**self).apply_deferred(world);
+    }
+
+    fn initialize(&mut self, world: &mut World) {
+        (**self).initialize(world);
+    }
+
+    fn is_initialized(&mut self, world: &mut World) -> bool {
+        (**self).is_initialized(world)
+    }
+
+    fn check_change_tick(&mut self, change_tick: ChangeTick) {
+        (**self).check_change_tick(change_tick);
+    }
 }
 
 // All systems implicitly implement IntoSystem.
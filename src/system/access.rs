@@ -0,0 +1,53 @@
+use core::any::TypeId;
+
+use bevy_utils::HashSet;
+use hecs::Fetch;
+
+/// Component types a system reads, writes or only filters on.
+/// Resources are components of the resource entity, so they are tracked the same way.
+#[derive(Default, Clone, Debug)]
+pub struct Access {
+    reads: HashSet<TypeId>,
+    writes: HashSet<TypeId>,
+    /// Types only checked for presence, like `With<T>`. Their data is never borrowed.
+    filters: HashSet<TypeId>,
+}
+
+impl Access {
+    pub fn add_read(&mut self, id: TypeId) {
+        self.reads.insert(id);
+    }
+    pub fn add_write(&mut self, id: TypeId) {
+        self.writes.insert(id);
+    }
+    pub fn add_filter(&mut self, id: TypeId) {
+        self.filters.insert(id);
+    }
+    /// Registers every component borrowed by the hecs query `Q`.
+    pub fn add_query<Q: hecs::Query>(&mut self) {
+        Q::Fetch::for_each_borrow(|id, unique| {
+            if unique { self.add_write(id) } else { self.add_read(id) }
+        });
+    }
+    pub fn reads(&self) -> impl Iterator<Item = &TypeId> { self.reads.iter() }
+    pub fn writes(&self) -> impl Iterator<Item = &TypeId> { self.writes.iter() }
+    pub fn filters(&self) -> impl Iterator<Item = &TypeId> { self.filters.iter() }
+    #[must_use]
+    pub fn has_read(&self, id: TypeId) -> bool { self.reads.contains(&id) }
+    #[must_use]
+    pub fn has_write(&self, id: TypeId) -> bool { self.writes.contains(&id) }
+
+    /// Returns true if both accesses can run at the same time.
+    /// Filters never conflict, since they don't touch component data.
+    #[must_use]
+    pub fn is_compatible(&self, other: &Access) -> bool {
+        self.writes.is_disjoint(&other.writes)
+            && self.writes.is_disjoint(&other.reads)
+            && self.reads.is_disjoint(&other.writes)
+    }
+    pub fn extend(&mut self, other: &Access) {
+        self.reads.extend(other.reads.iter().copied());
+        self.writes.extend(other.writes.iter().copied());
+        self.filters.extend(other.filters.iter().copied());
+    }
+}
@@ -0,0 +1,140 @@
+use core::fmt::Debug;
+
+use bevy_utils::tracing::warn;
+
+use super::{systeminput::SystemIn, IntoSystem, System};
+use crate::{ChangeTick, World};
+
+/// A system whose output is passed through `func`. Created by [`IntoSystem::map`].
+///
+/// Everything but the output is forwarded to the wrapped system, so it stays exclusive,
+/// deferred and lazily initialized exactly like the original.
+pub struct MapSystem<S, F> {
+    system: S,
+    func: F,
+}
+impl<S, F> MapSystem<S, F> {
+    pub fn new(system: S, func: F) -> Self {
+        Self { system, func }
+    }
+}
+
+impl<S, F, Out> System for MapSystem<S, F>
+where
+    S: System,
+    F: FnMut(S::Out) -> Out + Send + Sync + 'static,
+{
+    type In = S::In;
+    type Out = Out;
+
+    fn is_exclusive(&self) -> bool {
+        self.system.is_exclusive()
+    }
+
+    fn has_deferred(&self) -> bool {
+        self.system.has_deferred()
+    }
+
+    fn run_unchecked(&mut self, input: SystemIn<'_, Self>, world: &mut World) -> Self::Out {
+        (self.func)(self.system.run_unchecked(input, world))
+    }
+
+    fn apply_deferred(&mut self, world: &mut World) {
+        self.system.apply_deferred(world);
+    }
+
+    fn initialize(&mut self, world: &mut World) {
+        self.system.initialize(world);
+    }
+
+    fn is_initialized(&mut self, world: &mut World) -> bool {
+        self.system.is_initialized(world)
+    }
+
+    fn check_change_tick(&mut self, change_tick: ChangeTick) {
+        self.system.check_change_tick(change_tick);
+    }
+}
+
+pub type UnwrapSystem<S, T, E> = MapSystem<S, fn(Result<T, E>) -> T>;
+pub type WarnSystem<S, T, E> = MapSystem<S, fn(Result<T, E>)>;
+
+/// Adapters for systems returning a `Result`.
+pub trait IntoResultSystem<In, T, E, Marker>: IntoSystem<In, Result<T, E>, Marker>
+where
+    In: super::systeminput::SystemInput,
+    T: 'static,
+    E: Debug + 'static,
+{
+    /// Panics if the system returns an error.
+    fn unwrap(self) -> UnwrapSystem<Self::System, T, E> {
+        self.map(Result::unwrap as fn(Result<T, E>) -> T)
+    }
+
+    /// Logs errors returned by the system as warnings. Ok values are dropped.
+    fn warn(self) -> WarnSystem<Self::System, T, E> {
+        fn warn_err<T, E: Debug>(result: Result<T, E>) {
+            if let Err(err) = result {
+                warn!("system returned an error: {:?}", err);
+            }
+        }
+        self.map(warn_err as fn(Result<T, E>))
+    }
+}
+impl<In, T, E, Marker, S> IntoResultSystem<In, T, E, Marker> for S
+where
+    S: IntoSystem<In, Result<T, E>, Marker>,
+    In: super::systeminput::SystemInput,
+    T: 'static,
+    E: Debug + 'static,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::system::{systeminput::In, BoxedSystem};
+
+    use super::*;
+
+    fn double(In(v): In<u32>) -> u32 { v * 2 }
+    fn checked(In(v): In<u32>) -> Result<u32, String> {
+        if v > 10 { Err("too big".to_string()) } else { Ok(v) }
+    }
+    fn exclusive(_: &mut World) -> u8 { 1 }
+
+    #[test]
+    fn map_and_ignore() {
+        let mut w = World::new();
+        let mut s: BoxedSystem<In<u32>, String> = Box::new(double.map(|v: u32| v.to_string()));
+        assert_eq!("6", s.run(3, &mut w));
+        let mut s: BoxedSystem<In<u32>> = Box::new(double.ignore());
+        s.run(3, &mut w);
+    }
+
+    #[test]
+    fn unwrap_and_warn() {
+        let mut w = World::new();
+        let mut s = IntoSystem::into_system(checked.unwrap());
+        assert_eq!(4, s.run(4, &mut w));
+        let mut s: BoxedSystem<In<u32>> = Box::new(checked.warn());
+        s.run(11, &mut w);
+    }
+
+    #[test]
+    #[should_panic(expected = "too big")]
+    fn unwrap_panics() {
+        let mut w = World::new();
+        let mut s = IntoSystem::into_system(checked.unwrap());
+        s.run(11, &mut w);
+    }
+
+    #[test]
+    fn keeps_system_kind() {
+        let mut w = World::new();
+        let mut s: BoxedSystem = Box::new(exclusive.ignore());
+        assert!(s.is_exclusive());
+        assert!(!s.is_initialized(&mut w));
+        s.run((), &mut w);
+        assert!(s.is_initialized(&mut w));
+    }
+}
@@ -0,0 +1,184 @@
+use bevy_utils::tracing::warn;
+use hecs::{Bundle, DynamicBundle, Entity};
+
+use super::{deferred::SystemBuffer, systeminput::SystemInput, systemmeta::SystemMeta, systemparam::SystemParam, systemregistry::SystemId};
+use crate::{resource::Resource, ChangeTick, World};
+
+type Command = Box<dyn FnOnce(&mut World) + Send + Sync>;
+
+/// World mutations queued by [`Commands`], applied in order.
+///
+/// Unlike hecs' `CommandBuffer`, these go through the [`World`] methods,
+/// so component hooks run and removals are recorded.
+#[derive(Default)]
+pub struct CommandQueue {
+    commands: Vec<Command>,
+}
+
+impl CommandQueue {
+    pub fn push(&mut self, command: impl FnOnce(&mut World) + Send + Sync + 'static) {
+        self.commands.push(Box::new(command));
+    }
+    #[must_use]
+    pub fn len(&self) -> usize { self.commands.len() }
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.commands.is_empty() }
+    /// Runs the queued commands on `world`, leaving the queue empty.
+    pub fn apply(&mut self, world: &mut World) {
+        for command in self.commands.drain(..) {
+            command(world);
+        }
+    }
+}
+
+impl SystemBuffer for CommandQueue {
+    fn apply(&mut self, _: &SystemMeta, world: &mut World) {
+        CommandQueue::apply(self, world);
+    }
+}
+
+/// Queues structural changes, which are applied when the system applies its deferred buffers.
+pub struct Commands<'w, 's> {
+    queue: &'s mut CommandQueue,
+    entities: &'w hecs::World,
+}
+
+impl<'w, 's> Commands<'w, 's> {
+    /// Commands pushing to `queue`, reserving new entities in `entities`.
+    pub fn new(queue: &'s mut CommandQueue, entities: &'w hecs::World) -> Self {
+        Self { queue, entities }
+    }
+    /// Queues an arbitrary world mutation.
+    pub fn add(&mut self, command: impl FnOnce(&mut World) + Send + Sync + 'static) {
+        self.queue.push(command);
+    }
+    /// Reserves an entity now, and inserts `components` into it later.
+    pub fn spawn(&mut self, components: impl DynamicBundle + Send + Sync + 'static) -> EntityCommands<'_> {
+        let entity = self.entities.reserve_entity();
+        let mut entity_commands = self.entity(entity);
+        entity_commands.insert(components);
+        entity_commands
+    }
+    pub fn entity(&mut self, entity: Entity) -> EntityCommands<'_> {
+        EntityCommands { entity, queue: self.queue, entities: self.entities }
+    }
+    pub fn insert_resource<R: Resource>(&mut self, resource: R) {
+        self.add(move |world| world.insert_resource(resource));
+    }
+    /// Runs a registered system when the commands are applied, dropping its output.
+    pub fn run_system<O: 'static>(&mut self, id: SystemId<(), O>) {
+        self.run_system_with_input(id, ());
+    }
+    pub fn run_system_with_input<I: SystemInput + 'static, O: 'static>(&mut self, id: SystemId<I, O>, input: I::Inner<'static>)
+    where
+        I::Inner<'static>: Send + Sync,
+    {
+        self.add(move |world| {
+            if let Err(error) = world.run_system_with_input(id, input) {
+                warn!("could not run {id:?}: {error:?}");
+            }
+        });
+    }
+}
+
+/// Commands for a single entity, from [`Commands::spawn`] or [`Commands::entity`].
+/// Commands on an entity which is gone by the time they run are skipped with a warning.
+pub struct EntityCommands<'a> {
+    entity: Entity,
+    queue: &'a mut CommandQueue,
+    entities: &'a hecs::World,
+}
+
+impl EntityCommands<'_> {
+    #[must_use]
+    pub fn id(&self) -> Entity { self.entity }
+    /// Reserves an entity now, for commands on another entity than this one.
+    pub(crate) fn reserve(&mut self) -> EntityCommands<'_> {
+        let entity = self.entities.reserve_entity();
+        EntityCommands { entity, queue: self.queue, entities: self.entities }
+    }
+    pub fn insert(&mut self, components: impl DynamicBundle + Send + Sync + 'static) -> &mut Self {
+        let entity = self.entity;
+        self.queue.push(move |world| {
+            if world.insert(entity, components).is_err() {
+                warn!("could not insert components into {entity:?}, it does not exist");
+            }
+        });
+        self
+    }
+    /// Removes the components of `T`, if the entity has all of them.
+    pub fn remove<T: Bundle + 'static>(&mut self) -> &mut Self {
+        let entity = self.entity;
+        self.queue.push(move |world| {
+            let _ = world.remove::<T>(entity);
+        });
+        self
+    }
+    pub fn despawn(&mut self) {
+        let entity = self.entity;
+        self.queue.push(move |world| {
+            if world.despawn(entity).is_err() {
+                warn!("could not despawn {entity:?}, it does not exist");
+            }
+        });
+    }
+    /// Queues an arbitrary mutation of this entity.
+    pub fn add(&mut self, command: impl FnOnce(Entity, &mut World) + Send + Sync + 'static) -> &mut Self {
+        let entity = self.entity;
+        self.queue.push(move |world| command(entity, world));
+        self
+    }
+}
+
+impl SystemParam for Commands<'_, '_> {
+    type State = CommandQueue;
+
+    type Item<'world, 'state> = Commands<'world, 'state>;
+
+    fn init_state(_: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        system_meta.has_deferred = true;
+        CommandQueue::default()
+    }
+
+    fn get_param<'world, 'state>(
+        state: &'state mut Self::State,
+        _: &SystemMeta,
+        world: &'world World,
+        _: ChangeTick,
+    ) -> Self::Item<'world, 'state> {
+        Commands::new(state, &world.hworld)
+    }
+
+    fn apply(state: &mut Self::State, _: &SystemMeta, world: &mut World) {
+        state.apply(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{system::{IntoSystem, System}, Query};
+
+    use super::*;
+
+    struct Pos(i32);
+    struct Spawned(Entity);
+
+    #[test]
+    fn spawn_insert_despawn() {
+        let mut w = World::new();
+        let a = w.spawn((Pos(1),));
+        let mut s = IntoSystem::into_system(|mut commands: Commands, q: Query<&Pos>| {
+            for (e, p) in q.iter() {
+                let child = commands.spawn((Pos(p.0 * 10),)).id();
+                commands.entity(e).insert((Spawned(child),)).remove::<(Pos,)>();
+            }
+        });
+        s.run((), &mut w);
+        let child = w.get::<&Spawned>(a).unwrap().0;
+        assert!(w.get::<&Pos>(a).is_err());
+        assert_eq!(10, w.get::<&Pos>(child).unwrap().0);
+        let mut despawn = IntoSystem::into_system(move |mut commands: Commands| commands.entity(child).despawn());
+        despawn.run((), &mut w);
+        assert!(!w.contains(child));
+    }
+}
@@ -0,0 +1,112 @@
+use core::ops::{Deref, DerefMut};
+
+use hecs::CommandBuffer;
+
+use super::{systemmeta::SystemMeta, systemparam::SystemParam};
+use crate::{ChangeTick, World};
+
+/// A buffer of world mutations recorded while a system runs,
+/// and applied once the system's borrows are released.
+pub trait SystemBuffer: Default + Send + Sync + 'static {
+    fn apply(&mut self, system_meta: &SystemMeta, world: &mut World);
+}
+
+/// Gives a system mutable access to its own [`SystemBuffer`].
+/// The buffer is kept across runs and applied in [`System::apply_deferred`](super::System::apply_deferred).
+pub struct Deferred<'s, T: SystemBuffer>(pub(crate) &'s mut T);
+
+impl<T: SystemBuffer> Deref for Deferred<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
+impl<T: SystemBuffer> DerefMut for Deferred<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.0
+    }
+}
+
+impl<T: SystemBuffer> SystemParam for Deferred<'_, T> {
+    type State = T;
+
+    type Item<'world, 'state> = Deferred<'state, T>;
+
+    fn init_state(_: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        system_meta.has_deferred = true;
+        T::default()
+    }
+
+    fn get_param<'world, 'state>(
+        state: &'state mut Self::State,
+        _: &SystemMeta,
+        _: &'world World,
+        _: ChangeTick,
+    ) -> Self::Item<'world, 'state> {
+        Deferred(state)
+    }
+
+    fn apply(state: &mut Self::State, system_meta: &SystemMeta, world: &mut World) {
+        state.apply(system_meta, world);
+    }
+}
+
+/// Runs on the inner `hecs::World`, so hooks and observers don't run and removals aren't recorded.
+/// `Deferred<CommandQueue>` or [`Commands`](super::commands::Commands) go through the [`World`] methods instead.
+impl SystemBuffer for CommandBuffer {
+    fn apply(&mut self, _: &SystemMeta, world: &mut World) {
+        self.run_on(&mut world.hworld);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hecs::Entity;
+
+    use crate::{resource::Resource, system::{IntoSystem, System}, Query};
+
+    use super::*;
+
+    struct Pos(i32);
+
+    /// Records moved entities, and writes them into the index in one go.
+    #[derive(Default)]
+    struct IndexUpdates { moved: Vec<(Entity, i32)> }
+    #[derive(Default, Resource)]
+    struct SpatialIndex(Vec<(Entity, i32)>);
+    impl SystemBuffer for IndexUpdates {
+        fn apply(&mut self, _: &SystemMeta, world: &mut World) {
+            world.get_resource_mut::<SpatialIndex>().0.append(&mut self.moved);
+        }
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn track(q: Query<&Pos>, mut updates: Deferred<IndexUpdates>) {
+        for (e, p) in q.iter() {
+            updates.moved.push((e, p.0));
+        }
+    }
+
+    #[test]
+    fn applied_after_run() {
+        let mut w = World::new();
+        w.insert_resource(SpatialIndex::default());
+        let e = w.spawn((Pos(3),));
+        let mut s = IntoSystem::into_system(track);
+        s.run((), &mut w);
+        assert!(s.has_deferred());
+        assert_eq!(vec![(e, 3)], w.get_resource::<SpatialIndex>().0);
+    }
+
+    #[test]
+    fn command_buffer() {
+        let mut w = World::new();
+        let mut s = IntoSystem::into_system(|mut commands: Deferred<CommandBuffer>| {
+            commands.spawn((Pos(1),));
+        });
+        s.run((), &mut w);
+        s.run((), &mut w);
+        assert_eq!(2, w.query::<&Pos>().iter().count());
+    }
+}
@@ -2,7 +2,7 @@ use core::marker::PhantomData;
 
 use exclusivesystemparamfunction::ExclusiveSystemParamFunction;
 
-use crate::World;
+use crate::{changetick::{check_tick, MAX_CHANGE_AGE}, ChangeTick, World};
 
 use super::{exclusivesystemparam::ExclusiveSystemParam, systeminput::SystemIn, systemmeta::SystemMeta, IntoSystem, System};
 
@@ -40,12 +40,15 @@ where
     }
 
     fn run_unchecked(&mut self, input: SystemIn<'_, Self>, world: &mut World) -> Self::Out {
+        // same tick model as FunctionSystem, see the changetick module.
+        let change_tick = world.increment_change_tick();
         let params = F::Param::get_param(
             self.param_state.as_mut().expect(PARAM_MESSAGE),
             &self.system_meta,
+            change_tick,
         );
         let out = self.func.run(world, input, params);
-        self.system_meta.last_run = world.increment_change_tick();
+        self.system_meta.last_run = change_tick;
         return out;
     }
 
@@ -58,13 +61,17 @@ where
 
     #[inline]
     fn initialize(&mut self, world: &mut World) {
-        self.system_meta.last_run = 0;
+        self.system_meta.last_run = world.change_tick().wrapping_sub(MAX_CHANGE_AGE);
         self.param_state = Some(F::Param::init(world, &mut self.system_meta));
     }
 
     fn is_initialized(&mut self, _: &mut World) -> bool {
         self.param_state.is_some()
     }
+
+    fn check_change_tick(&mut self, change_tick: ChangeTick) {
+        check_tick(&mut self.system_meta.last_run, change_tick);
+    }
 }
 impl<Marker, F> IntoSystem<F::In, F::Out, (IsExclusiveFunctionSystem, Marker)> for F
 where
@@ -76,7 +83,7 @@ where
         ExclusiveFunctionSystem {
             func,
             param_state: None,
-            system_meta: SystemMeta::default(),
+            system_meta: SystemMeta::new::<F>(),
             marker: PhantomData,
         }
     }
@@ -1,4 +1,4 @@
-use crate::World;
+use crate::{ChangeTick, World};
 
 use super::systemmeta::SystemMeta;
 
@@ -13,9 +13,10 @@ pub trait ExclusiveSystemParam: Sized {
     fn init(world: &mut World, system_meta: &mut SystemMeta) -> Self::State;
 
     /// Creates a parameter to be passed into an [`ExclusiveSystemParamFunction`].
+    /// `change_tick` is the tick of this run, like in [`SystemParam::get_param`](super::systemparam::SystemParam::get_param).
     ///
     /// [`ExclusiveSystemParamFunction`]: super::ExclusiveSystemParamFunction
-    fn get_param<'s>(state: &'s mut Self::State, system_meta: &SystemMeta) -> Self::Item<'s>;
+    fn get_param<'s>(state: &'s mut Self::State, system_meta: &SystemMeta, change_tick: ChangeTick) -> Self::Item<'s>;
 }
 pub type ExclusiveSystemParamItem<'s, P> = <P as ExclusiveSystemParam>::Item<'s>;
 macro_rules! impl_exclusive_system_param_tuple {
@@ -37,10 +38,11 @@ macro_rules! impl_exclusive_system_param_tuple {
             fn get_param<'s>(
                 state: &'s mut Self::State,
                 system_meta: &SystemMeta,
+                change_tick: ChangeTick,
             ) -> Self::Item<'s> {
 
                 let ($($param,)*) = state;
-                ($($param::get_param($param, system_meta),)*)
+                ($($param::get_param($param, system_meta, change_tick),)*)
             }
         }
     };
@@ -2,7 +2,7 @@ use core::marker::PhantomData;
 
 use systemparamfunction::SystemParamFunction;
 
-use crate::World;
+use crate::{changetick::{check_tick, MAX_CHANGE_AGE}, ChangeTick, World};
 
 use super::{systeminput::SystemIn, systemmeta::SystemMeta, systemparam::SystemParam, IntoSystem, System};
 
@@ -60,6 +60,7 @@ where
             )
         };
         let out = self.func.run(input, params);
+        F::Param::unlock(self.param_state.as_mut().expect(PARAM_MESSAGE));
         self.system_meta.last_run = change_tick;
         out
     }
@@ -73,12 +74,16 @@ where
     #[inline]
     fn initialize(&mut self, world: &mut World) {
         self.param_state = Some(F::Param::init_state(world, &mut self.system_meta));
-        self.system_meta.last_run = 0;
+        self.system_meta.last_run = world.change_tick().wrapping_sub(MAX_CHANGE_AGE);
     }
 
     fn is_initialized(&mut self, _world: &mut World) -> bool {
         self.param_state.is_some()
     }
+
+    fn check_change_tick(&mut self, change_tick: ChangeTick) {
+        check_tick(&mut self.system_meta.last_run, change_tick);
+    }
 }
 
 
@@ -92,7 +97,7 @@ where
         FunctionSystem {
             func,
             param_state: None,
-            system_meta: SystemMeta::default(),
+            system_meta: SystemMeta::new::<F>(),
             marker: PhantomData,
         }
     }
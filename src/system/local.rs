@@ -0,0 +1,79 @@
+use core::ops::{Deref, DerefMut};
+
+use super::{exclusivesystemparam::ExclusiveSystemParam, systemmeta::SystemMeta, systemparam::SystemParam};
+use crate::{ChangeTick, World};
+
+/// A value private to one system, kept across its runs. Starts as `T::default()`.
+pub struct Local<'s, T: Default + Send + Sync + 'static>(pub(crate) &'s mut T);
+
+impl<T: Default + Send + Sync + 'static> Deref for Local<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
+impl<T: Default + Send + Sync + 'static> DerefMut for Local<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.0
+    }
+}
+
+impl<T: Default + Send + Sync + 'static> SystemParam for Local<'_, T> {
+    type State = T;
+
+    type Item<'world, 'state> = Local<'state, T>;
+
+    fn init_state(_: &mut World, _: &mut SystemMeta) -> Self::State {
+        T::default()
+    }
+
+    fn get_param<'world, 'state>(
+        state: &'state mut Self::State,
+        _: &SystemMeta,
+        _: &'world World,
+        _: ChangeTick,
+    ) -> Self::Item<'world, 'state> {
+        Local(state)
+    }
+}
+
+impl<T: Default + Send + Sync + 'static> ExclusiveSystemParam for Local<'_, T> {
+    type State = T;
+
+    type Item<'s> = Local<'s, T>;
+
+    fn init(_: &mut World, _: &mut SystemMeta) -> Self::State {
+        T::default()
+    }
+
+    fn get_param<'s>(state: &'s mut Self::State, _: &SystemMeta, _: ChangeTick) -> Self::Item<'s> {
+        Local(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::system::{IntoSystem, System};
+
+    use super::*;
+
+    #[test]
+    fn kept_across_runs() {
+        let mut w = World::new();
+        let mut s = IntoSystem::into_system(count);
+        s.run((), &mut w);
+        assert_eq!(2, s.run((), &mut w));
+        let mut s = IntoSystem::into_system(count_exclusive);
+        s.run((), &mut w);
+        assert_eq!(2, s.run((), &mut w));
+    }
+    fn count(mut runs: Local<u32>) -> u32 {
+        *runs += 1;
+        *runs
+    }
+    fn count_exclusive(_: &mut World, mut runs: Local<u32>) -> u32 {
+        *runs += 1;
+        *runs
+    }
+}
@@ -0,0 +1,47 @@
+use bevy_utils::Parallel;
+
+use super::{commands::{CommandQueue, Commands}, systemmeta::SystemMeta, systemparam::SystemParam};
+use crate::{ChangeTick, World};
+
+/// Records world mutations from many threads at once, e.g. inside
+/// [`QueryParIter::for_each`](super::query::pariter::QueryParIter::for_each).
+///
+/// Each thread gets its own [`CommandQueue`]. All of them are applied to the world when the system applies its deferred buffers,
+/// through the [`World`] methods like [`Commands`], so hooks and observers run.
+pub struct ParallelCommands<'w, 's> {
+    queues: &'s Parallel<CommandQueue>,
+    entities: &'w hecs::World,
+}
+
+impl ParallelCommands<'_, '_> {
+    /// Runs `f` with commands pushing to the queue of the current thread.
+    pub fn command_scope<R>(&self, f: impl FnOnce(Commands) -> R) -> R {
+        self.queues.scope(|queue| f(Commands::new(queue, self.entities)))
+    }
+}
+
+impl SystemParam for ParallelCommands<'_, '_> {
+    type State = Parallel<CommandQueue>;
+
+    type Item<'world, 'state> = ParallelCommands<'world, 'state>;
+
+    fn init_state(_: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        system_meta.has_deferred = true;
+        Parallel::default()
+    }
+
+    fn get_param<'world, 'state>(
+        state: &'state mut Self::State,
+        _: &SystemMeta,
+        world: &'world World,
+        _: ChangeTick,
+    ) -> Self::Item<'world, 'state> {
+        ParallelCommands { queues: state, entities: &world.hworld }
+    }
+
+    fn apply(state: &mut Self::State, _: &SystemMeta, world: &mut World) {
+        for queue in state.iter_mut() {
+            queue.apply(world);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use super::{systemmeta::SystemMeta, systemparam::{SystemParam, SystemParamItem}};
+use crate::{ChangeTick, World};
+
+/// Holds several params which may conflict with each other, like `&R` and `&mut R`,
+/// or two queries writing the same component.
+///
+/// Only one of them is accessible at a time, through `p0()`, `p1()` and so on.
+/// Every access releases the borrows of the previous one first.
+pub struct ParamSet<'w, 's, T: SystemParam> {
+    state: &'s mut ParamSetState<T::State>,
+    world: &'w World,
+    change_tick: ChangeTick,
+}
+
+pub struct ParamSetState<S> {
+    states: S,
+    /// Handed to the inner params, which only get one at a time.
+    meta: SystemMeta,
+}
+
+impl<T: SystemParam> SystemParam for ParamSet<'_, '_, T> {
+    type State = ParamSetState<T::State>;
+
+    type Item<'world, 'state> = ParamSet<'world, 'state, T>;
+
+    fn init_state(world: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        let mut meta = SystemMeta::default();
+        let states = T::init_state(world, &mut meta);
+        // the inner params are never live together, but the system still touches all of them.
+        system_meta.access.extend(&meta.access);
+        system_meta.has_deferred |= meta.has_deferred;
+        ParamSetState { states, meta }
+    }
+
+    fn get_param<'world, 'state>(
+        state: &'state mut Self::State,
+        system_meta: &SystemMeta,
+        world: &'world World,
+        change_tick: ChangeTick,
+    ) -> Self::Item<'world, 'state> {
+        state.meta.last_run = system_meta.last_run;
+        ParamSet { state, world, change_tick }
+    }
+
+    fn apply(state: &mut Self::State, system_meta: &SystemMeta, world: &mut World) {
+        T::apply(&mut state.states, system_meta, world);
+    }
+
+    fn unlock(state: &mut Self::State) {
+        T::unlock(&mut state.states);
+    }
+}
+
+impl<T: SystemParam> ParamSet<'_, '_, T> {
+    fn release(&mut self) {
+        T::unlock(&mut self.state.states);
+    }
+}
+impl<T: SystemParam> Drop for ParamSet<'_, '_, T> {
+    // the inner params are fetched after get_param returned, so their borrows are released here too.
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+macro_rules! impl_param_set {
+    ($(($index: tt, $param: ident, $fn_name: ident)),*) => {
+        impl<$($param: SystemParam,)*> ParamSet<'_, '_, ($($param,)*)> {
+            $(
+                /// Releases the previously accessed param and returns this one.
+                pub fn $fn_name(&mut self) -> SystemParamItem<'_, '_, $param> {
+                    self.release();
+                    $param::get_param(&mut self.state.states.$index, &self.state.meta, self.world, self.change_tick)
+                }
+            )*
+        }
+    };
+}
+impl_param_set!((0, P0, p0), (1, P1, p1));
+impl_param_set!((0, P0, p0), (1, P1, p1), (2, P2, p2));
+impl_param_set!((0, P0, p0), (1, P1, p1), (2, P2, p2), (3, P3, p3));
+impl_param_set!((0, P0, p0), (1, P1, p1), (2, P2, p2), (3, P3, p3), (4, P4, p4));
+impl_param_set!((0, P0, p0), (1, P1, p1), (2, P2, p2), (3, P3, p3), (4, P4, p4), (5, P5, p5));
+impl_param_set!((0, P0, p0), (1, P1, p1), (2, P2, p2), (3, P3, p3), (4, P4, p4), (5, P5, p5), (6, P6, p6));
+impl_param_set!((0, P0, p0), (1, P1, p1), (2, P2, p2), (3, P3, p3), (4, P4, p4), (5, P5, p5), (6, P6, p6), (7, P7, p7));
+
+#[cfg(test)]
+mod tests {
+    use core::any::TypeId;
+
+    use crate::{system::{IntoSystem, System}, Query, With};
+
+    use crate::resource::Resource;
+
+    use super::*;
+
+    #[derive(Resource)]
+    struct R1(u8);
+    struct Pos(i32);
+    struct Player;
+    type PosSet<'w, 's> = ParamSet<'w, 's, (Query<'static, &'static mut Pos>, Query<'static, &'static mut Pos, With<Player>>)>;
+
+    #[test]
+    fn resource_read_and_write() {
+        let mut w = World::new();
+        w.insert_resource(R1(4));
+        let mut s = IntoSystem::into_system(|mut set: ParamSet<(&R1, &mut R1)>| {
+            let old = set.p0().0;
+            set.p1().0 = old * 2;
+            set.p0().0
+        });
+        assert_eq!(8, s.run((), &mut w));
+        assert_eq!(8, w.get_resource::<R1>().0);
+    }
+
+    #[test]
+    fn conflicting_queries() {
+        let mut w = World::new();
+        w.spawn((Pos(1), Player));
+        w.spawn((Pos(10),));
+        let mut s = IntoSystem::into_system(|mut set: PosSet| {
+            for (_, p) in set.p0().iter_mut() {
+                p.0 += 1;
+            }
+            for (_, p) in set.p1().iter_mut() {
+                p.0 *= 100;
+            }
+        });
+        s.run((), &mut w);
+        let mut all: Vec<i32> = w.query::<&Pos>().iter().map(|(_, p)| p.0).collect();
+        all.sort_unstable();
+        assert_eq!(vec![11, 200], all);
+        assert!(s.system_meta.access.has_write(TypeId::of::<Pos>()));
+    }
+}
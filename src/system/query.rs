@@ -1,11 +1,16 @@
 pub mod view;
+pub mod queryparam;
+pub mod filter;
+pub mod pariter;
+pub mod combinations;
 use derive_more::derive::{Deref, DerefMut};
 use hecs::{PreparedQuery, PreparedQueryBorrow, PreparedQueryIter, World};
 
-use super::systemparam::SystemParam;
+use super::{exclusivesystemparam::ExclusiveSystemParam, systemmeta::SystemMeta, systemparam::SystemParam};
 pub struct QueryIterState<Q: hecs::Query + 'static> {
-    query: PreparedQuery<Q>,
+    // dropped before query, see ViewState.
     borrow: Option<PreparedQueryBorrow<'static, Q>>,
+    query: PreparedQuery<Q>,
 }
 unsafe impl<Q: hecs::Query> Send for QueryIterState<Q>{}
 unsafe impl<Q: hecs::Query> Sync for QueryIterState<Q>{}
@@ -23,12 +28,11 @@ impl<Q: hecs::Query> QueryIterState<Q> {
         // transmut changes lifetimes. So ensure lifetime gurantees manually.
         // resource is borrowed for world. So no dangling pointer.
         // lock by hecs on this column, will be gone when query_borrow drops.
-        // self.unlock() will drop it. When called from system.apply.
+        // self.unlock() will drop it, see SystemParam::unlock.
         let iter = unsafe { transmute::<hecs::PreparedQueryIter<'_, Q>, hecs::PreparedQueryIter<'_, Q>>(iter) };
         let borrow = unsafe { transmute::<hecs::PreparedQueryBorrow<'_, Q>, hecs::PreparedQueryBorrow<'_, Q>>(query_borrow) };
         self.borrow = Some(borrow);
         // # Safety. Borrow is actually from world too. It won't live past function call.
-        // unlock called by system.apply will remove hecs lock.
         return Some(iter);
     }
     pub fn unlock(&mut self) {
@@ -42,7 +46,8 @@ impl<Q: hecs::Query + 'static> SystemParam for PreparedQueryIter<'_, Q>
 
     type Item<'world, 'state> = PreparedQueryIter<'world, Q>;
 
-    fn init_state(_: &mut crate::World, _: &mut crate::system::systemmeta::SystemMeta) -> Self::State {
+    fn init_state(_: &mut crate::World, system_meta: &mut crate::system::systemmeta::SystemMeta) -> Self::State {
+        system_meta.access.add_query::<Q>();
         QueryIterState::default()
     }
 
@@ -55,7 +60,55 @@ impl<Q: hecs::Query + 'static> SystemParam for PreparedQueryIter<'_, Q>
         state.lock(world).unwrap()
     }
 
-    fn apply(state: &mut Self::State, _: &super::systemmeta::SystemMeta, _: &mut crate::World) {
+    fn unlock(state: &mut Self::State) {
         state.unlock();
     }
 }
+
+/// State of the `&mut PreparedQuery<Q>` exclusive system param.
+/// Keeps the prepared query across runs, so it is only re-prepared when archetypes change.
+#[derive(Deref, DerefMut)]
+pub struct PreparedQueryState<Q: hecs::Query + 'static>(PreparedQuery<Q>);
+unsafe impl<Q: hecs::Query> Send for PreparedQueryState<Q>{}
+unsafe impl<Q: hecs::Query> Sync for PreparedQueryState<Q>{}
+
+impl<Q: hecs::Query + 'static> ExclusiveSystemParam for &mut PreparedQuery<Q> {
+    type State = PreparedQueryState<Q>;
+
+    type Item<'s> = &'s mut PreparedQuery<Q>;
+
+    fn init(_: &mut crate::World, _: &mut SystemMeta) -> Self::State {
+        PreparedQueryState(PreparedQuery::default())
+    }
+
+    fn get_param<'s>(state: &'s mut Self::State, _: &SystemMeta, _: crate::ChangeTick) -> Self::Item<'s> {
+        &mut state.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::system::{IntoSystem, System};
+
+    use super::*;
+
+    struct Pos(i32);
+
+    #[test]
+    fn cached_prepared_query() {
+        let mut w = crate::World::new();
+        w.spawn((Pos(1),));
+        let mut s = IntoSystem::into_system(move_right);
+        s.run((), &mut w);
+        w.spawn((Pos(10),));
+        s.run((), &mut w);
+        let mut all: Vec<i32> = w.query::<&Pos>().iter().map(|(_, p)| p.0).collect();
+        all.sort_unstable();
+        assert_eq!(vec![3, 11], all);
+    }
+    fn move_right(world: &mut crate::World, query: &mut PreparedQuery<&mut Pos>) {
+        for (_, p) in query.query_mut(world) {
+            p.0 += 1;
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use hecs::{Entity, PreparedView, QueryShared};
+
+use super::filter::{Filtered, QueryFilter};
+
+/// Every unordered set of `K` distinct entities matching a [`Query`](super::queryparam::Query).
+///
+/// Created by `Query::iter_combinations` and `Query::iter_combinations_mut`.
+/// For shared queries this is an [`Iterator`]. Mutable queries must use [`fetch_next`](Self::fetch_next),
+/// which ties each combination to a borrow of the iterator, so two combinations never alias.
+pub struct QueryCombinationIter<'a, 'w, Q: hecs::Query + 'static, F: QueryFilter, const K: usize> {
+    view: &'a PreparedView<'w, Filtered<Q, F>>,
+    entities: Vec<Entity>,
+    /// Indices into `entities` of the next combination, always strictly increasing.
+    next: Option<[usize; K]>,
+}
+
+impl<'a, 'w, Q: hecs::Query + 'static, F: QueryFilter, const K: usize> QueryCombinationIter<'a, 'w, Q, F, K> {
+    pub(crate) fn new(view: &'a PreparedView<'w, Filtered<Q, F>>, entities: Vec<Entity>) -> Self {
+        let next = (K > 0 && K <= entities.len()).then(|| core::array::from_fn(|i| i));
+        Self { view, entities, next }
+    }
+
+    fn advance(&mut self) -> Option<[Entity; K]> {
+        let indices = self.next?;
+        let n = self.entities.len();
+        // find the rightmost index which can still move right, then reset everything after it.
+        let mut following = indices;
+        self.next = (0..K).rev().find(|&i| indices[i] < n - K + i).map(|i| {
+            following[i] += 1;
+            for j in i + 1..K {
+                following[j] = following[j - 1] + 1;
+            }
+            following
+        });
+        return Some(indices.map(|i| self.entities[i]));
+    }
+
+    /// Returns the next combination, with mutable access to each of its entities.
+    pub fn fetch_next(&mut self) -> Option<[(Entity, Q::Item<'_>); K]> {
+        let entities = self.advance()?;
+        // # Safety
+        // The entities of one combination are distinct, and the items can't outlive this borrow of self.
+        Some(entities.map(|e| (e, unsafe { self.view.get_unchecked(e) }.expect("entity matched the query when collected"))))
+    }
+}
+
+impl<'a, Q: hecs::Query + QueryShared + 'static, F: QueryFilter, const K: usize> Iterator
+    for QueryCombinationIter<'a, '_, Q, F, K>
+{
+    type Item = [(Entity, Q::Item<'a>); K];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entities = self.advance()?;
+        let view = self.view;
+        Some(entities.map(|e| (e, view.get(e).expect("entity matched the query when collected"))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{system::{IntoSystem, System}, Query, World};
+
+    struct Mass(u32);
+
+    #[test]
+    fn pairs() {
+        let mut w = World::new();
+        w.spawn_batch((0..5).map(|i| (Mass(i),)));
+        let mut s = IntoSystem::into_system(|q: Query<&Mass>| {
+            let mut pairs: Vec<(u32, u32)> = q.iter_combinations::<2>()
+                .map(|[(_, a), (_, b)]| (a.0.min(b.0), a.0.max(b.0)))
+                .collect();
+            pairs.sort_unstable();
+            pairs
+        });
+        let pairs = s.run((), &mut w);
+        assert_eq!(10, pairs.len());
+        pairs.windows(2).for_each(|p| assert_ne!(p[0], p[1]));
+        assert!(pairs.iter().all(|(a, b)| a != b));
+    }
+
+    #[test]
+    fn triples_mut() {
+        let mut w = World::new();
+        w.spawn_batch((0..6).map(|i| (Mass(i),)));
+        let mut s = IntoSystem::into_system(|mut q: Query<&mut Mass>| {
+            let mut combinations = q.iter_combinations_mut::<3>();
+            while let Some([(_, a), (_, b), (_, c)]) = combinations.fetch_next() {
+                a.0 += 1;
+                b.0 += 1;
+                c.0 += 1;
+            }
+        });
+        s.run((), &mut w);
+        // every entity is in C(5, 2) = 10 triples.
+        assert!(w.query::<&Mass>().iter().all(|(_, m)| m.0 >= 10));
+        let total: u32 = w.query::<&Mass>().iter().map(|(_, m)| m.0).sum();
+        assert_eq!(15 + 60, total);
+    }
+
+    #[test]
+    fn too_few_entities() {
+        let mut w = World::new();
+        w.spawn_batch((0..2).map(|i| (Mass(i),)));
+        let mut s = IntoSystem::into_system(|q: Query<&Mass>| q.iter_combinations::<3>().count());
+        assert_eq!(0, s.run((), &mut w));
+    }
+}
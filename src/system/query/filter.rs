@@ -0,0 +1,155 @@
+use core::{any::TypeId, marker::PhantomData};
+
+use hecs::Component;
+
+use crate::{relationship::Relationship, system::access::Access};
+
+/// Restricts which entities a [`Query`](super::queryparam::Query) yields, without fetching any data.
+///
+/// `()` lets everything through. Tuples of filters must all pass.
+pub trait QueryFilter: 'static {
+    /// A hecs query matching exactly the entities that pass this filter.
+    /// Only used to match archetypes, never borrowed.
+    type Matches: hecs::Query;
+
+    /// Registers the filtered types as non-data reads.
+    fn register(access: &mut Access);
+}
+
+/// `Q` restricted to the entities passing filter `F`.
+pub type Filtered<Q, F> = hecs::With<Q, <F as QueryFilter>::Matches>;
+
+/// Only entities having component `T`.
+pub struct With<T>(PhantomData<fn(T)>);
+/// Only entities not having component `T`.
+pub struct Without<T>(PhantomData<fn(T)>);
+/// Entities passing at least one of the filters in the tuple.
+pub struct Or<T>(PhantomData<fn(T)>);
+/// Only entities having relationship `R`. Also declares the read of `R::Target` which
+/// [`Query::iter_targeting`](super::queryparam::Query::iter_targeting) needs to pick those targeting one entity.
+pub struct Targeting<R>(PhantomData<fn(R)>);
+
+impl<T: Component> QueryFilter for With<T> {
+    type Matches = hecs::With<(), &'static T>;
+
+    fn register(access: &mut Access) {
+        access.add_filter(TypeId::of::<T>());
+    }
+}
+impl<T: Component> QueryFilter for Without<T> {
+    type Matches = hecs::Without<(), &'static T>;
+
+    fn register(access: &mut Access) {
+        access.add_filter(TypeId::of::<T>());
+    }
+}
+
+impl<R: Relationship> QueryFilter for Targeting<R> {
+    type Matches = hecs::With<(), &'static R>;
+
+    fn register(access: &mut Access) {
+        access.add_filter(TypeId::of::<R>());
+        access.add_read(TypeId::of::<R::Target>());
+    }
+}
+
+impl QueryFilter for () {
+    type Matches = ();
+
+    fn register(_: &mut Access) {}
+}
+impl<P0: QueryFilter> QueryFilter for (P0,) {
+    type Matches = P0::Matches;
+
+    fn register(access: &mut Access) {
+        P0::register(access);
+    }
+}
+impl<P0: QueryFilter> QueryFilter for Or<(P0,)> {
+    type Matches = P0::Matches;
+
+    fn register(access: &mut Access) {
+        P0::register(access);
+    }
+}
+// Longer tuples nest hecs With/Or over the first filter and the rest of the tuple.
+macro_rules! impl_query_filter_tuple {
+    ($first: ident, $($rest: ident),*) => {
+        impl<$first: QueryFilter, $($rest: QueryFilter),*> QueryFilter for ($first, $($rest,)*) {
+            type Matches = hecs::With<$first::Matches, <($($rest,)*) as QueryFilter>::Matches>;
+
+            fn register(access: &mut Access) {
+                $first::register(access);
+                $($rest::register(access);)*
+            }
+        }
+        impl<$first: QueryFilter, $($rest: QueryFilter),*> QueryFilter for Or<($first, $($rest,)*)> {
+            type Matches = hecs::Or<$first::Matches, <Or<($($rest,)*)> as QueryFilter>::Matches>;
+
+            fn register(access: &mut Access) {
+                $first::register(access);
+                $($rest::register(access);)*
+            }
+        }
+    };
+}
+impl_query_filter_tuple!(P0, P1);
+impl_query_filter_tuple!(P0, P1, P2);
+impl_query_filter_tuple!(P0, P1, P2, P3);
+impl_query_filter_tuple!(P0, P1, P2, P3, P4);
+impl_query_filter_tuple!(P0, P1, P2, P3, P4, P5);
+impl_query_filter_tuple!(P0, P1, P2, P3, P4, P5, P6);
+impl_query_filter_tuple!(P0, P1, P2, P3, P4, P5, P6, P7);
+
+#[cfg(test)]
+mod tests {
+    use core::any::TypeId;
+
+    use crate::{system::{query::queryparam::Query, IntoSystem, System}, World};
+
+    use super::*;
+
+    struct Player;
+    struct Dead;
+    struct Npc;
+    struct Health(u8);
+
+    fn sum<F: QueryFilter>(w: &mut World) -> u8 {
+        let mut s = IntoSystem::into_system(|q: Query<&Health, F>| q.iter().map(|(_, h)| h.0).sum::<u8>());
+        s.run((), w)
+    }
+
+    #[test]
+    fn with_without() {
+        let mut w = World::new();
+        w.spawn((Player, Health(1)));
+        w.spawn((Player, Dead, Health(2)));
+        w.spawn((Npc, Health(4)));
+        w.spawn((Health(8),));
+        assert_eq!(3, sum::<With<Player>>(&mut w));
+        assert_eq!(13, sum::<Without<Dead>>(&mut w));
+        assert_eq!(1, sum::<(With<Player>, Without<Dead>)>(&mut w));
+    }
+
+    #[test]
+    fn or() {
+        let mut w = World::new();
+        w.spawn((Player, Health(1)));
+        w.spawn((Player, Dead, Health(2)));
+        w.spawn((Npc, Health(4)));
+        w.spawn((Health(8),));
+        assert_eq!(7, sum::<Or<(With<Player>, With<Npc>)>>(&mut w));
+        assert_eq!(5, sum::<(Without<Dead>, Or<(With<Npc>, With<Player>)>)>(&mut w));
+    }
+
+    #[test]
+    fn filters_are_not_data_access() {
+        let mut s = IntoSystem::into_system(|_: Query<&mut Health, (With<Player>, Without<Dead>)>| {});
+        let mut w = World::new();
+        s.initialize(&mut w);
+        let access = &s.system_meta.access;
+        assert!(access.has_write(TypeId::of::<Health>()));
+        assert!(!access.has_read(TypeId::of::<Player>()));
+        assert_eq!(2, access.filters().count());
+    }
+}
@@ -0,0 +1,127 @@
+use core::{ops::Range, sync::atomic::{AtomicUsize, Ordering}};
+
+use hecs::{Archetype, Entity};
+
+use crate::{taskpool::TaskPool, Query};
+
+use super::{filter::{Filtered, QueryFilter}, queryparam::FilteredFetch};
+
+/// Batches per thread when no batch size is set. More than one, so uneven batches even out.
+const BATCHES_PER_THREAD: usize = 4;
+
+/// Runs a closure over the results of a [`Query`] on several threads.
+///
+/// Created by `Query::par_iter` and `Query::par_iter_mut`.
+/// Each matching archetype is split into batches of rows,
+/// which the threads of the [global task pool](TaskPool::global) pick up until none are left.
+pub struct QueryParIter<'a, 'w, Q: hecs::Query + 'static, F: QueryFilter> {
+    pub(crate) query: &'a Query<'w, Q, F>,
+    pub(crate) batch_size: Option<usize>,
+}
+
+/// Rows `rows` of `archetype`, read through `fetch`.
+struct Batch<'a, Fe> {
+    archetype: &'a Archetype,
+    fetch: &'a Fe,
+    rows: Range<usize>,
+}
+// # Safety. A fetch is a set of column pointers. Every batch is handled by one thread,
+// and the batches of one archetype cover distinct rows.
+unsafe impl<Fe> Sync for Batch<'_, Fe> {}
+
+impl<Q: hecs::Query + 'static, F: QueryFilter> QueryParIter<'_, '_, Q, F>
+where
+    for<'q> Q::Item<'q>: Send,
+{
+    /// Number of entities each thread handles at a time.
+    /// Defaults to splitting the matches evenly into a few batches per thread.
+    #[must_use]
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch size must be positive");
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    /// Calls `f` on every matching entity, from the threads of the global task pool.
+    pub fn for_each(self, f: impl Fn(Entity, Q::Item<'_>) + Send + Sync) {
+        let pool = TaskPool::global();
+        let archetypes: Vec<_> = self.query.archetypes().collect();
+        let batch_size = self.batch_size.unwrap_or_else(|| {
+            let len: usize = archetypes.iter().map(|(archetype, _)| archetype.len() as usize).sum();
+            len.div_ceil(pool.thread_num() * BATCHES_PER_THREAD).max(1)
+        });
+        let batches: Vec<Batch<'_, FilteredFetch<Q, F>>> = archetypes.iter()
+            .flat_map(|(archetype, fetch)| {
+                let len = archetype.len() as usize;
+                (0..len).step_by(batch_size).map(move |start| Batch {
+                    archetype,
+                    fetch,
+                    rows: start..len.min(start + batch_size),
+                })
+            })
+            .collect();
+        let next = AtomicUsize::new(0);
+        pool.broadcast(batches.len(), &|| {
+            while let Some(batch) = batches.get(next.fetch_add(1, Ordering::Relaxed)) {
+                for n in batch.rows.clone() {
+                    // # Safety
+                    // n is in bounds, and each row is in exactly one batch, taken by one thread.
+                    // So no two threads get a unique borrow of the same entity's components.
+                    // The view holds the column locks while the query is borrowed.
+                    let item = unsafe { <Filtered<Q, F> as hecs::Query>::get(batch.fetch, n) };
+                    f(self.query.entity_at(batch.archetype, n), item);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::{system::{parallelcommands::ParallelCommands, IntoSystem, System}, Query, World};
+
+    struct Pos(u32);
+    struct Marked;
+
+    #[test]
+    fn par_for_each_mut() {
+        let mut w = World::new();
+        w.spawn_batch((0..10_000).map(|i| (Pos(i),)));
+        let mut s = IntoSystem::into_system(|mut q: Query<&mut Pos>| {
+            q.par_for_each_mut(64, |_, p| p.0 += 1);
+        });
+        s.run((), &mut w);
+        let sum: u32 = w.query::<&Pos>().iter().map(|(_, p)| p.0).sum();
+        assert_eq!((1..=10_000).sum::<u32>(), sum);
+    }
+
+    #[test]
+    fn par_iter_default_batches() {
+        let mut w = World::new();
+        w.spawn_batch((0..10_000).map(|i| (Pos(i),)));
+        let mut s = IntoSystem::into_system(|q: Query<&Pos>| {
+            let count = AtomicU32::new(0);
+            q.par_iter().for_each(|_, _| { count.fetch_add(1, Ordering::Relaxed); });
+            count.into_inner()
+        });
+        assert_eq!(10_000, s.run((), &mut w));
+    }
+
+    #[test]
+    fn parallel_commands() {
+        let mut w = World::new();
+        w.spawn_batch((0..10_000).map(|i| (Pos(i),)));
+        let mut s = IntoSystem::into_system(|q: Query<&Pos>, commands: ParallelCommands| {
+            q.par_iter().batch_size(100).for_each(|e, p| {
+                if p.0 % 2 == 0 {
+                    commands.command_scope(|mut c| { c.entity(e).insert((Marked,)); });
+                }
+            });
+        });
+        s.run((), &mut w);
+        assert!(s.has_deferred());
+        assert_eq!(5_000, w.query::<&Marked>().iter().count());
+    }
+}
@@ -0,0 +1,304 @@
+use hecs::{Archetype, Entity, Fetch, PreparedView, QueryShared};
+
+use crate::{relationship::{Relationship, RelationshipTarget}, system::systemparam::SystemParam};
+
+use super::{combinations::QueryCombinationIter, filter::{Filtered, QueryFilter}, pariter::QueryParIter, view::ViewState};
+
+/// Error returned by [`Query::get_single`] and [`Query::get_single_mut`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuerySingleError {
+    NoEntities,
+    MultipleEntities,
+}
+
+/// Random access and repeatable iteration over the entities matching `Q` and passing filter `F`.
+///
+/// Unlike [`hecs::PreparedQueryIter`] this can be iterated any number of times in one run.
+/// Columns are locked for the whole run and unlocked by [`SystemParam::unlock`].
+pub struct Query<'w, Q: hecs::Query + 'static, F: QueryFilter = ()> {
+    view: PreparedView<'w, Filtered<Q, F>>,
+    // the view has no shared iteration, so `iter` walks the archetypes of the world itself.
+    world: &'w hecs::World,
+}
+
+pub(crate) type FilteredFetch<Q, F> = <Filtered<Q, F> as hecs::Query>::Fetch;
+
+impl<'w, Q: hecs::Query + 'static, F: QueryFilter> Query<'w, Q, F> {
+    pub(crate) fn new(view: PreparedView<'w, Filtered<Q, F>>, world: &'w hecs::World) -> Self {
+        Self { view, world }
+    }
+    fn view(&self) -> &PreparedView<'w, Filtered<Q, F>> {
+        &self.view
+    }
+    fn view_mut(&mut self) -> &mut PreparedView<'w, Filtered<Q, F>> {
+        &mut self.view
+    }
+
+    /// The non empty archetypes matching the query, each with the fetch reading its columns.
+    /// They are the archetypes the view has locked, so the fetches stay valid for `'w`.
+    pub(crate) fn archetypes(&self) -> impl Iterator<Item = (&'w Archetype, FilteredFetch<Q, F>)> + use<'w, Q, F> {
+        self.world.archetypes()
+            .filter(|archetype| !archetype.is_empty())
+            .filter_map(|archetype| {
+                let state = FilteredFetch::<Q, F>::prepare(archetype)?;
+                Some((archetype, FilteredFetch::<Q, F>::execute(archetype, state)))
+            })
+    }
+
+    /// The entity stored at row `n` of `archetype`.
+    pub(crate) fn entity_at(&self, archetype: &Archetype, n: usize) -> Entity {
+        // # Safety. Ids stored in an archetype belong to live entities.
+        unsafe { self.world.find_entity_from_id(archetype.ids()[n]) }
+    }
+
+    /// Iterates over all matching entities. Can be called any number of times.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Q::Item<'_>)> + '_
+    where
+        Q: QueryShared,
+    {
+        self.archetypes().flat_map(move |(archetype, fetch)| {
+            (0..archetype.len() as usize).map(move |n| {
+                // # Safety
+                // n is in bounds. The view holds the column locks while self is borrowed,
+                // and Q is QueryShared, so no unique borrow can be live at the same time.
+                let item = unsafe { <Filtered<Q, F> as hecs::Query>::get(&fetch, n) };
+                (self.entity_at(archetype, n), item)
+            })
+        })
+    }
+
+    /// Iterates over all matching entities, allowing mutable access.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, Q::Item<'_>)> + '_ {
+        self.view_mut().iter_mut()
+    }
+
+    /// Returns the query item of `entity`, or None if it doesn't match the query.
+    #[must_use]
+    pub fn get(&self, entity: Entity) -> Option<Q::Item<'_>>
+    where
+        Q: QueryShared,
+    {
+        self.view().get(entity)
+    }
+
+    /// The items of `entities`, skipping those which don't match the query.
+    /// E.g. the sources listed in a [`RelationshipTarget`](crate::relationship::RelationshipTarget).
+    pub fn iter_many<'a, I>(&'a self, entities: I) -> impl Iterator<Item = (Entity, Q::Item<'a>)> + use<'a, 'w, Q, F, I>
+    where
+        Q: QueryShared,
+        I: IntoIterator<Item = &'a Entity>,
+    {
+        entities.into_iter().filter_map(|&entity| Some((entity, self.get(entity)?)))
+    }
+
+    /// The items of the entities whose relationship `R` targets `target`, in the order they were related.
+    /// Filter the query with [`Targeting<R>`](super::filter::Targeting), which declares the read of the target's list.
+    pub fn iter_targeting<R: Relationship>(&self, target: Entity) -> impl Iterator<Item = (Entity, Q::Item<'_>)> + '_
+    where
+        Q: QueryShared,
+    {
+        let sources = self.world.get::<&R::Target>(target).map(|t| t.sources().to_vec()).unwrap_or_default();
+        sources.into_iter().filter_map(move |entity| Some((entity, self.get(entity)?)))
+    }
+
+    /// Returns the query item of `entity`, or None if it doesn't match the query.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<Q::Item<'_>> {
+        self.view_mut().get_mut(entity)
+    }
+
+    /// Like [`get_mut`](Self::get_mut) for several entities at once.
+    ///
+    /// # Panics
+    /// If any entity is repeated, since that would alias mutable borrows.
+    pub fn get_many_mut<const N: usize>(&mut self, entities: [Entity; N]) -> [Option<Q::Item<'_>>; N] {
+        for (i, entity) in entities.iter().enumerate() {
+            assert!(!entities[..i].contains(entity), "Query::get_many_mut called with a repeated entity");
+        }
+        self.view_mut().get_many_mut(entities)
+    }
+
+    /// Returns the only matching entity.
+    pub fn get_single(&self) -> Result<(Entity, Q::Item<'_>), QuerySingleError>
+    where
+        Q: QueryShared,
+    {
+        single(self.iter())
+    }
+
+    /// Returns the only matching entity, allowing mutable access.
+    pub fn get_single_mut(&mut self) -> Result<(Entity, Q::Item<'_>), QuerySingleError> {
+        single(self.iter_mut())
+    }
+
+    /// Like [`get_single`](Self::get_single) but panics if there isn't exactly one match.
+    #[must_use]
+    pub fn single(&self) -> (Entity, Q::Item<'_>)
+    where
+        Q: QueryShared,
+    {
+        self.get_single().expect("Query::single expects exactly one matching entity")
+    }
+
+    /// Like [`get_single_mut`](Self::get_single_mut) but panics if there isn't exactly one match.
+    pub fn single_mut(&mut self) -> (Entity, Q::Item<'_>) {
+        self.get_single_mut().expect("Query::single_mut expects exactly one matching entity")
+    }
+
+    /// Returns true if no entity matches the query.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.archetypes().next().is_none()
+    }
+
+    /// Returns true if `entity` matches the query.
+    #[must_use]
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.view().contains(entity)
+    }
+
+    fn entities(&self) -> Vec<Entity> {
+        self.archetypes()
+            .flat_map(|(archetype, _)| (0..archetype.len() as usize).map(move |n| self.entity_at(archetype, n)))
+            .collect()
+    }
+
+    /// Iterates over all matching entities on several threads.
+    #[must_use]
+    pub fn par_iter(&self) -> QueryParIter<'_, 'w, Q, F>
+    where
+        Q: QueryShared,
+    {
+        QueryParIter { query: self, batch_size: None }
+    }
+
+    /// Iterates over all matching entities on several threads, allowing mutable access.
+    pub fn par_iter_mut(&mut self) -> QueryParIter<'_, 'w, Q, F> {
+        QueryParIter { query: self, batch_size: None }
+    }
+
+    /// Shorthand for `par_iter_mut().batch_size(batch_size).for_each(f)`.
+    pub fn par_for_each_mut(&mut self, batch_size: usize, f: impl Fn(Entity, Q::Item<'_>) + Send + Sync)
+    where
+        for<'q> Q::Item<'q>: Send,
+    {
+        self.par_iter_mut().batch_size(batch_size).for_each(f);
+    }
+
+    /// Iterates over every unordered set of `K` distinct matching entities.
+    #[must_use]
+    pub fn iter_combinations<const K: usize>(&self) -> QueryCombinationIter<'_, 'w, Q, F, K>
+    where
+        Q: QueryShared,
+    {
+        QueryCombinationIter::new(self.view(), self.entities())
+    }
+
+    /// Like [`iter_combinations`](Self::iter_combinations), allowing mutable access.
+    /// Use [`QueryCombinationIter::fetch_next`] to step through it.
+    pub fn iter_combinations_mut<const K: usize>(&mut self) -> QueryCombinationIter<'_, 'w, Q, F, K> {
+        let entities = self.entities();
+        QueryCombinationIter::new(self.view(), entities)
+    }
+}
+
+fn single<T>(mut iter: impl Iterator<Item = T>) -> Result<T, QuerySingleError> {
+    let first = iter.next().ok_or(QuerySingleError::NoEntities)?;
+    if iter.next().is_some() {
+        return Err(QuerySingleError::MultipleEntities);
+    }
+    return Ok(first);
+}
+
+impl<Q: hecs::Query + 'static, F: QueryFilter> SystemParam for Query<'_, Q, F> {
+    type State = ViewState<Filtered<Q, F>>;
+
+    type Item<'world, 'state> = Query<'world, Q, F>;
+
+    fn init_state(_: &mut crate::World, system_meta: &mut crate::system::systemmeta::SystemMeta) -> Self::State {
+        system_meta.access.add_query::<Q>();
+        F::register(&mut system_meta.access);
+        ViewState::default()
+    }
+
+    fn get_param<'world, 'state>(
+        state: &'state mut Self::State,
+        _: &crate::system::systemmeta::SystemMeta,
+        world: &'world crate::World,
+        _: crate::ChangeTick,
+    ) -> Self::Item<'world, 'state> {
+        Query::new(state.lock(world).unwrap(), &world.hworld)
+    }
+
+    fn unlock(state: &mut Self::State) {
+        state.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{system::{IntoSystem, System}, World};
+
+    use super::*;
+
+    struct Pos(i32);
+    struct Vel(i32);
+
+    #[test]
+    fn iterate_twice() {
+        let mut w = World::new();
+        w.spawn((Pos(1), Vel(2)));
+        w.spawn((Pos(3), Vel(4)));
+        let mut s = IntoSystem::into_system(move_twice);
+        s.run((), &mut w);
+        let total: i32 = w.query::<&Pos>().iter().map(|(_, p)| p.0).sum();
+        assert_eq!(16, total);
+    }
+    fn move_twice(mut q: Query<(&mut Pos, &Vel)>) {
+        for _ in 0..2 {
+            for (_, (p, v)) in q.iter_mut() {
+                p.0 += v.0;
+            }
+        }
+    }
+
+    #[test]
+    fn get_and_single() {
+        let mut w = World::new();
+        let e = w.spawn((Pos(5),));
+        let mut s = IntoSystem::into_system(move |q: Query<&Pos>| {
+            assert!(!q.is_empty());
+            assert!(q.contains(e));
+            assert_eq!(5, q.get(e).unwrap().0);
+            assert_eq!(e, q.single().0);
+            assert_eq!(q.iter().count(), q.iter().count());
+        });
+        s.run((), &mut w);
+        w.spawn((Pos(6),));
+        let mut s = IntoSystem::into_system(|q: Query<&Pos>| q.get_single().err());
+        assert_eq!(Some(QuerySingleError::MultipleEntities), s.run((), &mut w));
+    }
+
+    #[test]
+    fn nested_iter() {
+        let mut w = World::new();
+        let e = w.spawn((Pos(1),));
+        w.spawn((Pos(2), Vel(0)));
+        let mut s = IntoSystem::into_system(move |q: Query<&Pos, crate::Without<Vel>>| {
+            let held = q.get(e).unwrap();
+            let pairs = q.iter().flat_map(|(_, a)| q.iter().map(move |(_, b)| a.0 * b.0)).sum::<i32>();
+            (held.0, pairs)
+        });
+        assert_eq!((1, 1), s.run((), &mut w));
+    }
+
+    #[test]
+    #[should_panic(expected = "repeated entity")]
+    fn get_many_mut_aliasing() {
+        let mut w = World::new();
+        let e = w.spawn((Pos(5),));
+        let mut s = IntoSystem::into_system(move |mut q: Query<&mut Pos>| {
+            let _ = q.get_many_mut([e, e]);
+        });
+        s.run((), &mut w);
+    }
+}
@@ -4,8 +4,10 @@ use hecs::{PreparedQuery, PreparedQueryBorrow, PreparedQueryIter, PreparedView,
 use crate::system::systemparam::SystemParam;
 
 pub struct ViewState<Q: hecs::Query + 'static> {
-    query: PreparedQuery<Q>,
+    // borrow must be declared first, so it is dropped (and releases its locks)
+    // before the prepared query it points into.
     borrow: Option<PreparedQueryBorrow<'static, Q>>,
+    query: PreparedQuery<Q>,
 }
 unsafe impl<Q: hecs::Query> Send for ViewState<Q>{}
 unsafe impl<Q: hecs::Query> Sync for ViewState<Q>{}
@@ -23,12 +25,11 @@ impl<Q: hecs::Query> ViewState<Q> {
         // transmut changes lifetimes. So ensure lifetime gurantees manually.
         // resource is borrowed for world. So no dangling pointer.
         // lock by hecs on this column, will be gone when query_borrow drops.
-        // self.unlock() will drop it. When called from system.apply.
+        // self.unlock() will drop it, see SystemParam::unlock.
         let view = unsafe { transmute::<hecs::PreparedView<'_, Q>, hecs::PreparedView<'_, Q>>(view) };
         let borrow = unsafe { transmute::<hecs::PreparedQueryBorrow<'_, Q>, hecs::PreparedQueryBorrow<'_, Q>>(query_borrow) };
         self.borrow = Some(borrow);
         // # Safety. Borrow is actually from world too. It won't live past function call.
-        // unlock called by system.apply will remove hecs lock.
         return Some(view);
     }
     pub fn unlock(&mut self) {
@@ -42,7 +43,8 @@ impl<Q: hecs::Query + 'static> SystemParam for PreparedView<'_, Q>
 
     type Item<'world, 'state> = PreparedView<'world, Q>;
 
-    fn init_state(_: &mut crate::World, _: &mut crate::system::systemmeta::SystemMeta) -> Self::State {
+    fn init_state(_: &mut crate::World, system_meta: &mut crate::system::systemmeta::SystemMeta) -> Self::State {
+        system_meta.access.add_query::<Q>();
         ViewState::<Q>::default()
     }
 
@@ -55,7 +57,7 @@ impl<Q: hecs::Query + 'static> SystemParam for PreparedView<'_, Q>
         state.lock(world).unwrap()
     }
 
-    fn apply(state: &mut Self::State, _: &crate::system::systemmeta::SystemMeta, _: &mut crate::World) {
+    fn unlock(state: &mut Self::State) {
         state.unlock();
     }
 }
@@ -0,0 +1,144 @@
+use super::{exclusivesystemparam::ExclusiveSystemParam, systemmeta::SystemMeta, systemparam::SystemParam};
+use crate::{changetick::is_newer_than, ChangeTick, World};
+
+/// The change ticks of the running system, see the [`changetick`](crate::changetick) module.
+/// Works in both normal and exclusive systems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemChangeTick {
+    last_run: ChangeTick,
+    this_run: ChangeTick,
+}
+
+impl SystemChangeTick {
+    /// The tick of the previous run of this system.
+    #[must_use]
+    pub fn last_run(&self) -> ChangeTick { self.last_run }
+    /// The tick of the current run of this system.
+    #[must_use]
+    pub fn this_run(&self) -> ChangeTick { self.this_run }
+    /// Returns true if `tick` came after the previous run of this system.
+    #[must_use]
+    pub fn is_changed(&self, tick: ChangeTick) -> bool {
+        is_newer_than(tick, self.last_run, self.this_run)
+    }
+}
+
+impl SystemParam for SystemChangeTick {
+    type State = ();
+
+    type Item<'world, 'state> = SystemChangeTick;
+
+    fn init_state(_: &mut World, _: &mut SystemMeta) -> Self::State {}
+
+    fn get_param<'world, 'state>(
+        (): &'state mut Self::State,
+        system_meta: &SystemMeta,
+        _: &'world World,
+        change_tick: ChangeTick,
+    ) -> Self::Item<'world, 'state> {
+        SystemChangeTick { last_run: system_meta.last_run, this_run: change_tick }
+    }
+}
+
+impl ExclusiveSystemParam for SystemChangeTick {
+    type State = ();
+
+    type Item<'s> = SystemChangeTick;
+
+    fn init(_: &mut World, _: &mut SystemMeta) -> Self::State {}
+
+    fn get_param<'s>((): &'s mut Self::State, system_meta: &SystemMeta, change_tick: ChangeTick) -> Self::Item<'s> {
+        SystemChangeTick { last_run: system_meta.last_run, this_run: change_tick }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resource::Resource, system::{IntoSystem, System}};
+
+    use super::*;
+
+    /// Tick at which `Stamp` was last written.
+    #[derive(Resource)]
+    struct Stamp(ChangeTick);
+
+    fn ticks(ticks: SystemChangeTick) -> SystemChangeTick { ticks }
+    fn exclusive_ticks(_: &mut World, ticks: SystemChangeTick) -> SystemChangeTick { ticks }
+    fn write_stamp(ticks: SystemChangeTick, stamp: &mut Stamp) {
+        stamp.0 = ticks.this_run();
+    }
+    fn exclusive_write_stamp(world: &mut World, ticks: SystemChangeTick) {
+        world.get_resource_mut::<Stamp>().0 = ticks.this_run();
+    }
+    fn sees_stamp(ticks: SystemChangeTick, stamp: &Stamp) -> bool {
+        ticks.is_changed(stamp.0)
+    }
+    fn exclusive_sees_stamp(world: &mut World, ticks: SystemChangeTick) -> bool {
+        let stamp = world.get_resource::<Stamp>().0;
+        ticks.is_changed(stamp)
+    }
+
+    #[test]
+    fn both_kinds_tick_before_running() {
+        let mut w = World::new();
+        let mut normal = IntoSystem::into_system(ticks);
+        let mut exclusive = IntoSystem::into_system(exclusive_ticks);
+        let n1 = normal.run((), &mut w);
+        assert_eq!(w.change_tick(), n1.this_run());
+        let e1 = exclusive.run((), &mut w);
+        assert_eq!(w.change_tick(), e1.this_run());
+        assert_eq!(n1.this_run() + 1, e1.this_run());
+        let n2 = normal.run((), &mut w);
+        let e2 = exclusive.run((), &mut w);
+        assert_eq!(n1.this_run(), n2.last_run());
+        assert_eq!(e1.this_run(), e2.last_run());
+    }
+
+    #[test]
+    fn changes_seen_across_kinds() {
+        let mut w = World::new();
+        w.insert_resource(Stamp(0));
+        let mut normal_write = IntoSystem::into_system(write_stamp);
+        let mut exclusive_write = IntoSystem::into_system(exclusive_write_stamp);
+        let mut normal_read = IntoSystem::into_system(sees_stamp);
+        let mut exclusive_read = IntoSystem::into_system(exclusive_sees_stamp);
+        // the first run sees everything as changed.
+        assert!(normal_read.run((), &mut w));
+        assert!(exclusive_read.run((), &mut w));
+        assert!(!normal_read.run((), &mut w));
+        assert!(!exclusive_read.run((), &mut w));
+
+        normal_write.run((), &mut w);
+        assert!(exclusive_read.run((), &mut w));
+        assert!(normal_read.run((), &mut w));
+        assert!(!exclusive_read.run((), &mut w));
+
+        exclusive_write.run((), &mut w);
+        assert!(normal_read.run((), &mut w));
+        assert!(exclusive_read.run((), &mut w));
+        assert!(!normal_read.run((), &mut w));
+    }
+
+    #[test]
+    fn own_writes_are_not_new_next_run() {
+        let mut w = World::new();
+        w.insert_resource(Stamp(0));
+        let mut s = IntoSystem::into_system(read_then_write);
+        assert!(s.run((), &mut w));
+        assert!(!s.run((), &mut w));
+        // a nested system run by an exclusive system gets a later tick than its caller.
+        let mut nested = IntoSystem::into_system(run_nested);
+        let (outer, inner) = nested.run((), &mut w);
+        assert!(inner.this_run() > outer.this_run());
+        assert_eq!(w.change_tick(), inner.this_run());
+    }
+    fn read_then_write(world: &mut World, ticks: SystemChangeTick) -> bool {
+        let mut stamp = world.get_resource_mut::<Stamp>();
+        let changed = ticks.is_changed(stamp.0);
+        stamp.0 = ticks.this_run();
+        changed
+    }
+    fn run_nested(world: &mut World, outer: SystemChangeTick) -> (SystemChangeTick, SystemChangeTick) {
+        (outer, world.run_system_once(ticks))
+    }
+}
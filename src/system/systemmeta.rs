@@ -1,7 +1,19 @@
+use std::borrow::Cow;
+
 use crate::ChangeTick;
 
+use super::access::Access;
+
 #[derive(Default)]
 pub struct SystemMeta {
+    pub name: Cow<'static, str>,
     pub last_run: ChangeTick,
     pub has_deferred: bool,
+    /// World data accessed by this system's params. Registered in `init_state`.
+    pub access: Access,
+}
+impl SystemMeta {
+    pub(crate) fn new<F>() -> Self {
+        Self { name: core::any::type_name::<F>().into(), ..Default::default() }
+    }
 }
@@ -0,0 +1,66 @@
+use std::borrow::Cow;
+
+use derive_more::derive::Deref;
+
+use super::{exclusivesystemparam::ExclusiveSystemParam, systemmeta::SystemMeta, systemparam::SystemParam};
+use crate::{ChangeTick, World};
+
+/// The name of the running system, by default the type name of its function.
+#[derive(Deref, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemName<'s>(&'s str);
+
+impl SystemName<'_> {
+    #[must_use]
+    pub fn name(&self) -> &str { self.0 }
+}
+
+impl SystemParam for SystemName<'_> {
+    type State = Cow<'static, str>;
+
+    type Item<'world, 'state> = SystemName<'state>;
+
+    fn init_state(_: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        system_meta.name.clone()
+    }
+
+    fn get_param<'world, 'state>(
+        state: &'state mut Self::State,
+        _: &SystemMeta,
+        _: &'world World,
+        _: ChangeTick,
+    ) -> Self::Item<'world, 'state> {
+        SystemName(state)
+    }
+}
+
+impl ExclusiveSystemParam for SystemName<'_> {
+    type State = Cow<'static, str>;
+
+    type Item<'s> = SystemName<'s>;
+
+    fn init(_: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        system_meta.name.clone()
+    }
+
+    fn get_param<'s>(state: &'s mut Self::State, _: &SystemMeta, _: ChangeTick) -> Self::Item<'s> {
+        SystemName(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::system::{IntoSystem, System};
+
+    use super::*;
+
+    #[test]
+    fn function_name() {
+        let mut w = World::new();
+        let mut s = IntoSystem::into_system(named);
+        assert!(s.run((), &mut w).ends_with("named"));
+        let mut s = IntoSystem::into_system(named_exclusive);
+        assert!(s.run((), &mut w).ends_with("named_exclusive"));
+    }
+    fn named(name: SystemName) -> String { name.to_string() }
+    fn named_exclusive(_: &mut World, name: SystemName) -> String { name.to_string() }
+}
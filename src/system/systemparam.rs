@@ -21,7 +21,19 @@ pub trait SystemParam: Sized {
     /// [`Commands`]: crate::prelude::Commands
     #[inline]
     #[allow(unused_variables)]
-    fn apply(state: &mut Self::State, system_meta: &SystemMeta, world: &mut World);
+    fn apply(state: &mut Self::State, system_meta: &SystemMeta, world: &mut World) {}
+
+    /// Releases the world borrows taken by [`get_param`](SystemParam::get_param).
+    ///
+    /// Params reading columns (queries, views, resources) keep the hecs borrow in their state,
+    /// with its lifetime extended, and drop it here.
+    /// The system calls this right after its function returns, before any [`apply`](SystemParam::apply).
+    /// Releasing in `apply` instead would keep later params of a tuple locked
+    /// while earlier ones, e.g. `Commands`, mutate the world.
+    /// [`ParamSet`](super::paramset::ParamSet) also calls it to release one param before handing out the next.
+    #[inline]
+    #[allow(unused_variables)]
+    fn unlock(state: &mut Self::State) {}
 
     /// Creates a parameter to be passed into a [`SystemParamFunction`].
     ///
@@ -60,6 +72,10 @@ macro_rules! impl_system_param_tuple {
                 $($param::apply($param, _system_meta, _world);)*
             }
             #[inline]
+            fn unlock(($($param,)*): &mut Self::State) {
+                $($param::unlock($param);)*
+            }
+            #[inline]
             #[allow(clippy::unused_unit)]
             fn get_param<'w, 's>(
                 state: &'s mut Self::State,
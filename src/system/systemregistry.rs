@@ -0,0 +1,222 @@
+use core::{any::TypeId, fmt::Debug, hash::Hash, marker::PhantomData};
+
+use hecs::Entity;
+
+use super::{systeminput::SystemInput, BoxedSystem, IntoSystem, System};
+use crate::{ChangeTick, World};
+
+/// Handle to a system stored in a [`World`] by [`World::register_system`].
+pub struct SystemId<I: SystemInput + 'static = (), O: 'static = ()> {
+    entity: Entity,
+    marker: PhantomData<fn(I) -> O>,
+}
+impl<I: SystemInput, O> SystemId<I, O> {
+    /// The entity holding the system.
+    #[must_use]
+    pub fn entity(&self) -> Entity { self.entity }
+}
+// Manual impls, so they don't require I and O to implement these too.
+impl<I: SystemInput, O> Clone for SystemId<I, O> {
+    fn clone(&self) -> Self { *self }
+}
+impl<I: SystemInput, O> Copy for SystemId<I, O> {}
+impl<I: SystemInput, O> PartialEq for SystemId<I, O> {
+    fn eq(&self, other: &Self) -> bool { self.entity == other.entity }
+}
+impl<I: SystemInput, O> Eq for SystemId<I, O> {}
+impl<I: SystemInput, O> Hash for SystemId<I, O> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) { self.entity.hash(state); }
+}
+impl<I: SystemInput, O> Debug for SystemId<I, O> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("SystemId").field(&self.entity).finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisteredSystemError {
+    /// The id was never registered, or the system was removed.
+    SystemIdNotRegistered,
+    /// The system tried to run itself.
+    Recursive,
+    /// The system tried to remove itself while running. It stays registered.
+    Running,
+}
+
+/// Component holding a registered system. Taken out while the system runs.
+struct RegisteredSystem<I: SystemInput, O> {
+    system: Option<BoxedSystem<I, O>>,
+}
+
+fn check_registered_systems<I: SystemInput + 'static, O: 'static>(world: &mut World, change_tick: ChangeTick) {
+    for (_, registered) in world.hworld.query_mut::<&mut RegisteredSystem<I, O>>() {
+        // a running system is checked by whoever holds it.
+        if let Some(system) = registered.system.as_mut() {
+            system.check_change_tick(change_tick);
+        }
+    }
+}
+
+impl World {
+    /// Stores `system` in the world, to be run later with [`run_system`](World::run_system).
+    pub fn register_system<I, O, M>(&mut self, system: impl IntoSystem<I, O, M>) -> SystemId<I, O>
+    where
+        I: SystemInput + 'static,
+        O: 'static,
+    {
+        let system: BoxedSystem<I, O> = Box::new(IntoSystem::into_system(system));
+        let entity = self.spawn((RegisteredSystem { system: Some(system) },));
+        self.tick_checks.entry(TypeId::of::<RegisteredSystem<I, O>>())
+            .or_insert(check_registered_systems::<I, O>);
+        SystemId { entity, marker: PhantomData }
+    }
+
+    /// Removes a registered system, returning it.
+    /// A running system can't be removed, since it is put back once it returns.
+    pub fn remove_system<I: SystemInput + 'static, O: 'static>(&mut self, id: SystemId<I, O>)
+        -> Result<BoxedSystem<I, O>, RegisteredSystemError> {
+        let running = self.hworld.get::<&RegisteredSystem<I, O>>(id.entity)
+            .map_err(|_| RegisteredSystemError::SystemIdNotRegistered)?
+            .system.is_none();
+        if running {
+            return Err(RegisteredSystemError::Running);
+        }
+        let registered = self.hworld.remove_one::<RegisteredSystem<I, O>>(id.entity)
+            .map_err(|_| RegisteredSystemError::SystemIdNotRegistered)?;
+        let _ = self.hworld.despawn(id.entity);
+        Ok(registered.system.expect("system is not running"))
+    }
+
+    pub fn run_system<O: 'static>(&mut self, id: SystemId<(), O>) -> Result<O, RegisteredSystemError> {
+        self.run_system_with_input(id, ())
+    }
+
+    /// Runs a registered system, initializing it on its first run and applying its deferred buffers.
+    pub fn run_system_with_input<I: SystemInput + 'static, O: 'static>(
+        &mut self,
+        id: SystemId<I, O>,
+        input: I::Inner<'_>,
+    ) -> Result<O, RegisteredSystemError> {
+        let mut system = self.hworld.get::<&mut RegisteredSystem<I, O>>(id.entity)
+            .map_err(|_| RegisteredSystemError::SystemIdNotRegistered)?
+            .system.take()
+            .ok_or(RegisteredSystemError::Recursive)?;
+        let out = system.run(input, self);
+        // the entity may have been despawned while the system ran.
+        if let Ok(mut registered) = self.hworld.get::<&mut RegisteredSystem<I, O>>(id.entity) {
+            registered.system = Some(system);
+        }
+        return Ok(out);
+    }
+
+    /// Runs `system` once, without keeping it. Its state is dropped afterwards.
+    pub fn run_system_once<O, M>(&mut self, system: impl IntoSystem<(), O, M>) -> O {
+        self.run_system_once_with((), system)
+    }
+
+    pub fn run_system_once_with<I: SystemInput, O, M>(&mut self, input: I::Inner<'_>, system: impl IntoSystem<I, O, M>) -> O {
+        let mut system = IntoSystem::into_system(system);
+        system.run(input, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resource::Resource, system::{commands::Commands, local::Local, systeminput::In}};
+
+    use super::*;
+
+    #[derive(Resource)]
+    struct Counter(u32);
+
+    fn increment(counter: &mut Counter) -> u32 {
+        counter.0 += 1;
+        counter.0
+    }
+    #[allow(clippy::needless_pass_by_value)]
+    fn add(amount: In<u32>, counter: &mut Counter) {
+        counter.0 += amount.0;
+    }
+    fn runs(mut runs: Local<u32>) -> u32 {
+        *runs += 1;
+        *runs
+    }
+
+    #[test]
+    fn run_by_id() {
+        let mut w = World::new();
+        w.insert_resource(Counter(0));
+        let inc = w.register_system(increment);
+        let add = w.register_system(add);
+        assert_eq!(Ok(1), w.run_system(inc));
+        assert_eq!(Ok(()), w.run_system_with_input(add, 10));
+        assert_eq!(Ok(12), w.run_system(inc));
+        w.remove_system(inc).unwrap();
+        assert_eq!(Err(RegisteredSystemError::SystemIdNotRegistered), w.run_system(inc));
+    }
+
+    #[test]
+    fn state_is_kept() {
+        let mut w = World::new();
+        let id = w.register_system(runs);
+        w.run_system(id).unwrap();
+        assert_eq!(Ok(2), w.run_system(id));
+        assert_eq!(1, w.run_system_once(runs));
+    }
+
+    #[test]
+    fn from_exclusive_system() {
+        let mut w = World::new();
+        w.insert_resource(Counter(0));
+        let inc = w.register_system(increment);
+        w.insert_resource(Callback(inc));
+        w.run_system_once(run_callback);
+        assert_eq!(1, w.get_resource::<Counter>().0);
+    }
+
+    #[test]
+    fn registered_ticks_are_checked() {
+        let mut w = World::new();
+        w.insert_resource(Counter(0));
+        let id = w.register_system(increment);
+        w.run_system(id).unwrap();
+        w.change_tick = w.change_tick.wrapping_add(3_000_000_000);
+        w.check_change_ticks();
+        assert_eq!(w.change_tick, w.last_check_tick);
+        assert_eq!(Ok(2), w.run_system(id));
+    }
+    #[derive(Resource)]
+    struct Callback(SystemId<(), u32>);
+    fn run_callback(world: &mut World) {
+        let id = world.get_resource::<Callback>().0;
+        world.run_system(id).unwrap();
+    }
+
+    #[test]
+    fn from_commands() {
+        let mut w = World::new();
+        w.insert_resource(Counter(0));
+        let inc = w.register_system(increment);
+        let add = w.register_system(add);
+        w.run_system_once(move |mut commands: Commands| {
+            commands.run_system(inc);
+            commands.run_system_with_input(add, 5);
+        });
+        assert_eq!(6, w.get_resource::<Counter>().0);
+    }
+
+    #[test]
+    fn remove_while_running() {
+        let mut w = World::new();
+        let id = w.register_system(remove_self);
+        w.insert_resource(SelfId(id));
+        assert_eq!(Ok(Err(RegisteredSystemError::Running)), w.run_system(id));
+        assert!(w.remove_system(id).is_ok());
+    }
+    #[derive(Resource)]
+    struct SelfId(SystemId<(), Result<(), RegisteredSystemError>>);
+    fn remove_self(world: &mut World) -> Result<(), RegisteredSystemError> {
+        let id = world.get_resource::<SelfId>().0;
+        world.remove_system(id).map(|_| ())
+    }
+}
@@ -0,0 +1,160 @@
+use core::{marker::PhantomData, mem::ManuallyDrop, ops::{Deref, DerefMut}};
+
+use super::{exclusivesystemparam::ExclusiveSystemParam, systemmeta::SystemMeta, systemparam::{SystemParam, SystemParamItem}};
+use crate::{changetick::{check_tick, MAX_CHANGE_AGE}, ChangeTick, World};
+
+/// Fetches [`SystemParam`]s from a world outside of a system, e.g. in exclusive systems or tests.
+///
+/// `init_state` runs once in [`new`](Self::new). Each [`get`](Self::get) returns a guard which
+/// derefs to the params, and releases their world borrows when dropped.
+/// Deferred buffers, like [`ParallelCommands`](super::parallelcommands::ParallelCommands),
+/// are only applied by [`apply`](Self::apply).
+pub struct SystemState<P: SystemParam + 'static> {
+    meta: SystemMeta,
+    param_state: P::State,
+}
+
+impl<P: SystemParam + 'static> SystemState<P> {
+    pub fn new(world: &mut World) -> Self {
+        let mut meta = SystemMeta {
+            last_run: world.change_tick().wrapping_sub(MAX_CHANGE_AGE),
+            ..SystemMeta::default()
+        };
+        let param_state = P::init_state(world, &mut meta);
+        Self { meta, param_state }
+    }
+
+    pub fn meta(&self) -> &SystemMeta { &self.meta }
+
+    /// Fetches the params from a shared world.
+    pub fn get<'w, 's>(&'s mut self, world: &'w World) -> SystemParamGuard<'w, 's, P> {
+        let change_tick = world.change_tick();
+        let state: *mut P::State = &raw mut self.param_state;
+        // # Safety. The guard only uses the pointer again after the item is dropped.
+        let item = P::get_param(unsafe { &mut *state }, &self.meta, world, change_tick);
+        self.meta.last_run = change_tick;
+        SystemParamGuard { item: ManuallyDrop::new(item), state, marker: PhantomData }
+    }
+
+    /// Fetches the params from a uniquely borrowed world, advancing its change tick like a system run does.
+    pub fn get_mut<'w, 's>(&'s mut self, world: &'w mut World) -> SystemParamGuard<'w, 's, P> {
+        world.increment_change_tick();
+        self.get(world)
+    }
+
+    /// Clamps the stored last run tick, see [`System::check_change_tick`](super::System::check_change_tick).
+    pub fn check_change_tick(&mut self, change_tick: ChangeTick) {
+        check_tick(&mut self.meta.last_run, change_tick);
+    }
+
+    /// Applies the deferred buffers of the params, like [`System::apply_deferred`](super::System::apply_deferred).
+    pub fn apply(&mut self, world: &mut World) {
+        P::apply(&mut self.param_state, &self.meta, world);
+    }
+}
+
+/// The params fetched by [`SystemState::get`]. Releases their world borrows when dropped.
+pub struct SystemParamGuard<'w, 's, P: SystemParam + 'static> {
+    item: ManuallyDrop<SystemParamItem<'w, 's, P>>,
+    state: *mut P::State,
+    marker: PhantomData<&'s mut P::State>,
+}
+
+impl<'w, 's, P: SystemParam> Deref for SystemParamGuard<'w, 's, P> {
+    type Target = SystemParamItem<'w, 's, P>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.item
+    }
+}
+impl<P: SystemParam> DerefMut for SystemParamGuard<'_, '_, P> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.item
+    }
+}
+impl<P: SystemParam> Drop for SystemParamGuard<'_, '_, P> {
+    fn drop(&mut self) {
+        // # Safety. item borrowed the state, it is gone before the state is touched again.
+        unsafe {
+            ManuallyDrop::drop(&mut self.item);
+            P::unlock(&mut *self.state);
+        }
+    }
+}
+
+impl<P: SystemParam + 'static> ExclusiveSystemParam for &mut SystemState<P> {
+    type State = SystemState<P>;
+
+    type Item<'s> = &'s mut SystemState<P>;
+
+    fn init(world: &mut World, _: &mut SystemMeta) -> Self::State {
+        SystemState::new(world)
+    }
+
+    fn get_param<'s>(state: &'s mut Self::State, _: &SystemMeta, _: ChangeTick) -> Self::Item<'s> {
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{system::{parallelcommands::ParallelCommands, paramset::ParamSet, IntoSystem, System}, Query};
+
+    use crate::resource::Resource;
+
+    use super::*;
+
+    #[derive(Resource)]
+    struct R1(u8);
+    struct Pos(i32);
+    struct Spawned;
+
+    #[test]
+    fn get_and_apply() {
+        let mut w = World::new();
+        w.insert_resource(R1(2));
+        w.spawn((Pos(1),));
+        let mut state = SystemState::<(Query<&mut Pos>, &R1, ParallelCommands)>::new(&mut w);
+        {
+            let mut params = state.get_mut(&mut w);
+            let (q, r1, commands) = &mut *params;
+            for (_, p) in q.iter_mut() {
+                p.0 += i32::from(r1.0);
+            }
+            commands.command_scope(|mut c| { c.spawn((Spawned,)); });
+        }
+        assert_eq!(0, w.query::<&Spawned>().iter().count());
+        state.apply(&mut w);
+        assert_eq!(1, w.query::<&Spawned>().iter().count());
+        // borrows were released, so the world can be changed and fetched again.
+        w.spawn((Pos(10),));
+        let mut params = state.get(&w);
+        assert_eq!(13, params.0.iter_mut().map(|(_, p)| p.0).sum::<i32>());
+    }
+
+    #[test]
+    fn exclusive_param() {
+        let mut w = World::new();
+        w.insert_resource(R1(5));
+        let mut s = IntoSystem::into_system(bump_r1);
+        s.run((), &mut w);
+        s.run((), &mut w);
+        assert_eq!(7, w.get_resource::<R1>().0);
+    }
+    fn bump_r1(world: &mut World, state: &mut SystemState<&mut R1>) {
+        state.get_mut(world).0 += 1;
+    }
+
+    #[test]
+    fn param_set_releases() {
+        let mut w = World::new();
+        w.insert_resource(R1(1));
+        let mut state = SystemState::<ParamSet<(&R1, &mut R1)>>::new(&mut w);
+        let mut set = state.get_mut(&mut w);
+        let old = set.p0().0;
+        set.p1().0 = old * 3;
+        drop(set);
+        // a borrow left behind by the param set would make this panic.
+        assert_eq!(3, w.get_resource::<R1>().0);
+    }
+}
@@ -0,0 +1,147 @@
+//! A pool of worker threads, started once and reused by every parallel query.
+
+use core::cell::Cell;
+use std::{panic::{self, AssertUnwindSafe}, sync::{mpsc, Arc, Condvar, Mutex, OnceLock}};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+std::thread_local! {
+    static IS_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Long lived worker threads, which run jobs handed to them by [`broadcast`](Self::broadcast).
+pub struct TaskPool {
+    sender: Mutex<mpsc::Sender<Job>>,
+    threads: usize,
+}
+
+impl TaskPool {
+    /// The pool shared by the whole process, with one thread per cpu.
+    /// Its workers are spawned on first use.
+    pub fn global() -> &'static TaskPool {
+        static POOL: OnceLock<TaskPool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            TaskPool::new(std::thread::available_parallelism().map_or(1, core::num::NonZero::get))
+        })
+    }
+
+    /// A pool running jobs on `threads` threads, the one calling [`broadcast`](Self::broadcast) included.
+    /// So `threads - 1` workers are spawned.
+    ///
+    /// # Panics
+    /// If a worker thread can't be spawned.
+    #[must_use]
+    pub fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 1..threads {
+            let receiver = Arc::clone(&receiver);
+            std::thread::Builder::new()
+                .name(format!("bhecs worker {i}"))
+                .spawn(move || {
+                    IS_WORKER.set(true);
+                    loop {
+                        // bound first, so the receiver is unlocked while the job runs.
+                        let job = receiver.lock().unwrap().recv();
+                        // the sender lives as long as the pool, so this only ends when the pool is dropped.
+                        let Ok(job) = job else { break };
+                        job();
+                    }
+                })
+                .expect("failed to spawn task pool worker");
+        }
+        Self { sender: Mutex::new(sender), threads: threads.max(1) }
+    }
+
+    /// Number of threads jobs run on, the calling one included.
+    #[must_use]
+    pub fn thread_num(&self) -> usize {
+        self.threads
+    }
+
+    /// Calls `f` once on each of up to `threads` threads at the same time, one of them being the calling thread.
+    /// Returns when every call has returned.
+    ///
+    /// Inside a worker `f` only runs on the calling thread,
+    /// so a nested broadcast never waits on workers which are busy waiting themselves.
+    ///
+    /// # Panics
+    /// If any of the calls panicked.
+    pub fn broadcast(&self, threads: usize, f: &(dyn Fn() + Sync)) {
+        let helpers = if IS_WORKER.get() { 0 } else { threads.min(self.threads).saturating_sub(1) };
+        if helpers == 0 {
+            f();
+            return;
+        }
+        // shared, as a worker may still be inside count_down when the wait below returns.
+        let latch = Arc::new(Latch { state: Mutex::new((helpers, false)), done: Condvar::new() });
+        // # Safety
+        // Only the lifetime is extended. Nothing touches f once its job counted the latch down,
+        // and every count down is awaited below, even if f panics on this thread.
+        let task = unsafe { core::mem::transmute::<&(dyn Fn() + Sync), &'static (dyn Fn() + Sync)>(f) };
+        {
+            let sender = self.sender.lock().unwrap();
+            for _ in 0..helpers {
+                let latch = Arc::clone(&latch);
+                let job = move || {
+                    let ok = panic::catch_unwind(AssertUnwindSafe(task)).is_ok();
+                    latch.count_down(ok);
+                };
+                sender.send(Box::new(job)).expect("task pool workers stopped");
+            }
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        let helper_panicked = latch.wait();
+        if let Err(payload) = result {
+            panic::resume_unwind(payload);
+        }
+        assert!(!helper_panicked, "a task pool job panicked");
+    }
+}
+
+/// Counts running helper jobs of one broadcast down to zero.
+struct Latch {
+    /// Jobs still running, and whether any of them panicked.
+    state: Mutex<(usize, bool)>,
+    done: Condvar,
+}
+
+impl Latch {
+    fn count_down(&self, ok: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.0 -= 1;
+        state.1 |= !ok;
+        drop(state);
+        self.done.notify_all();
+    }
+
+    /// Blocks until every job finished. Returns whether any of them panicked.
+    fn wait(&self) -> bool {
+        let state = self.done.wait_while(self.state.lock().unwrap(), |state| state.0 > 0).unwrap();
+        state.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn broadcast_reuses_workers() {
+        let pool = TaskPool::new(4);
+        let calls = AtomicUsize::new(0);
+        for _ in 0..10 {
+            pool.broadcast(4, &|| { calls.fetch_add(1, Ordering::Relaxed); });
+        }
+        assert_eq!(40, calls.into_inner());
+    }
+
+    #[test]
+    #[should_panic(expected = "a task pool job panicked")]
+    fn helper_panic() {
+        let pool = TaskPool::new(2);
+        pool.broadcast(2, &|| assert!(!IS_WORKER.get(), "ran on a worker"));
+    }
+}
@@ -0,0 +1,284 @@
+//! Moving entities from one [`World`] to another, e.g. to splice in a chunk built in a staging world.
+//!
+//! Components are moved as they are, like despawning and spawning without hooks, observers or
+//! removal tracking, so they are assumed consistent with each other. Registered
+//! [`Relationship`](crate::relationship::Relationship)s, like [`Parent`](crate::hierarchy::Parent), and the
+//! [`with_map_entities`](crate::typeregistry::RegisterType::with_map_entities) components registered in
+//! the destination's [`TypeRegistry`], or else the source's, get their references remapped.
+use core::any::TypeId;
+
+use bevy_utils::HashSet;
+use hecs::Entity;
+
+use crate::{
+    entitymap::EntityMap,
+    typeregistry::{MapEntitiesFn, TypeRegistry},
+    World,
+};
+
+/// Error from [`World::transfer_entity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferError {
+    NoSuchEntity,
+    /// The entity, or one of its descendants, is the resource entity, which never moves.
+    ResourceEntity,
+    /// The entity, or one of its descendants, is an observer or registered system,
+    /// whose state belongs to the world it was made in.
+    HoldsSystem,
+}
+
+/// Which resource wins when both worlds of a [`World::merge`] have one of the same type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceMerge {
+    /// Keep the resource of the world merged into.
+    Keep,
+    /// Replace it with the one of the merged world.
+    Replace,
+}
+
+impl World {
+    /// Moves `entity` and its descendants into `other`, returning the new id of `entity`.
+    /// They are detached first from every entity they are related to outside them, including the parent of `entity`.
+    pub fn transfer_entity(&mut self, entity: Entity, other: &mut World) -> Result<Entity, TransferError> {
+        if !self.hworld.contains(entity) {
+            return Err(TransferError::NoSuchEntity);
+        }
+        let entities = self.subtree(entity);
+        if entities.contains(&self.resource_entity()) {
+            return Err(TransferError::ResourceEntity);
+        }
+        if entities.iter().any(|&entity| self.holds_system(entity)) {
+            return Err(TransferError::HoldsSystem);
+        }
+        let moved: HashSet<Entity> = entities.iter().copied().collect();
+        let detach_fns: Vec<_> = self.relationships.values().map(|relationship| relationship.detach).collect();
+        for detach in detach_fns {
+            detach(self, &moved);
+        }
+        let map = self.move_entities(&entities, other);
+        Ok(map.map(entity))
+    }
+
+    /// Moves every entity of `other` into this world, returning their new ids.
+    /// Resources are combined according to `resources`, except the [`TypeRegistry`], which keeps the
+    /// registrations of this world and gains those of `other`'s other types. Systems and observers
+    /// registered in `other` are dropped with it, since their state belongs to `other`.
+    pub fn merge(&mut self, mut other: World, resources: ResourceMerge) -> EntityMap {
+        let entities: Vec<Entity> = other.hworld.iter()
+            .map(|entity| entity.entity())
+            .filter(|&entity| entity != other.resource_entity() && !other.holds_system(entity))
+            .collect();
+        let map = other.move_entities(&entities, self);
+        let their_registry = other.hworld.remove_one::<TypeRegistry>(other.resource_entity()).ok();
+
+        // combined in a staging world, inserting the winning resources last.
+        let resource_entity = self.resource_entity();
+        let theirs = other.hworld.take(other.resource_entity()).expect("resource entity exists");
+        let ours = self.hworld.take(resource_entity).expect("resource entity exists");
+        let mut staging = hecs::World::new();
+        let combined = match resources {
+            ResourceMerge::Keep => {
+                let combined = staging.spawn(theirs);
+                staging.insert(combined, ours).expect("just spawned");
+                combined
+            }
+            ResourceMerge::Replace => {
+                let combined = staging.spawn(ours);
+                staging.insert(combined, theirs).expect("just spawned");
+                combined
+            }
+        };
+        self.hworld.spawn_at(resource_entity, staging.take(combined).expect("just spawned"));
+        if let Some(registry) = their_registry {
+            self.type_registry_mut().merge(registry);
+        }
+        map
+    }
+
+    /// Moves `entities` from this world into `other`, remapping references between them.
+    fn move_entities(&mut self, entities: &[Entity], other: &mut World) -> EntityMap {
+        // relationships known to either world are remapped through their registration only.
+        let mut relationships = self.relationships.clone();
+        for (&type_id, &relationship) in &other.relationships {
+            relationships.entry(type_id).or_insert(relationship);
+        }
+        let related: Vec<TypeId> = relationships.iter()
+            .flat_map(|(&type_id, relationship)| [type_id, relationship.target])
+            .collect();
+        let registry = |world: &World| world.hworld.get::<&TypeRegistry>(world.resource_entity()).ok()
+            .map(|registry| registry.iter()
+                .filter(|registration| !related.contains(&registration.type_id()))
+                .filter_map(|registration| registration.map_entities)
+                .collect::<Vec<MapEntitiesFn>>());
+        let mut map_fns = registry(other).or_else(|| registry(self)).unwrap_or_default();
+        map_fns.extend(relationships.values().map(|relationship| relationship.map_entities));
+
+        let mut map = EntityMap::default();
+        for &entity in entities {
+            let Ok(components) = self.hworld.take(entity) else { continue };
+            let moved = other.hworld.spawn(components);
+            map.insert(entity, moved);
+        }
+        for (_, moved) in map.iter() {
+            for map_entities in &map_fns {
+                map_entities(&other.hworld, moved, &map);
+            }
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entitymap::MapEntities,
+        event::Event,
+        hierarchy::{Children, Parent},
+        observer::Trigger,
+        relationship::{Relationship, RelationshipTarget},
+        resource::Resource,
+    };
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Target(Entity);
+    impl MapEntities for Target {
+        fn map_entities(&mut self, map: &EntityMap) { self.0 = map.map(self.0); }
+    }
+    #[derive(Debug, PartialEq, Resource)]
+    struct Level(u32);
+    #[derive(Debug, PartialEq, Resource)]
+    struct Seed(u32);
+    struct Ping;
+    impl Event for Ping {}
+    struct Likes(Entity);
+    struct LikedBy(Vec<Entity>);
+    impl Relationship for Likes {
+        type Target = LikedBy;
+
+        fn target(&self) -> Entity { self.0 }
+        fn set_target(&mut self, target: Entity) { self.0 = target; }
+    }
+    impl RelationshipTarget for LikedBy {
+        fn from_sources(sources: Vec<Entity>) -> Self { Self(sources) }
+        fn sources(&self) -> &[Entity] { &self.0 }
+        fn sources_mut(&mut self) -> &mut Vec<Entity> { &mut self.0 }
+    }
+
+    #[test]
+    fn transfer_subtree() {
+        let mut main = World::new();
+        let _ = main.type_registry_mut().register::<Target>().with_map_entities();
+        let mut other = World::new();
+        let parent = main.spawn(());
+        let e = main.spawn((5u32,));
+        let child = main.spawn((Target(e),));
+        main.add_child(parent, e).unwrap();
+        main.add_child(e, child).unwrap();
+
+        let moved = main.transfer_entity(e, &mut other).unwrap();
+        assert!(!main.contains(e) && !main.contains(child));
+        assert!(main.get::<&Children>(parent).is_err());
+        assert_eq!(5, *other.get::<&u32>(moved).unwrap());
+        assert!(other.get::<&Parent>(moved).is_err());
+        let moved_child = other.get::<&Children>(moved).unwrap()[0];
+        assert_eq!(moved, other.get::<&Parent>(moved_child).unwrap().get());
+        assert_eq!(Target(moved), *other.get::<&Target>(moved_child).unwrap());
+    }
+
+    #[test]
+    fn transfer_relationships() {
+        let mut main = World::new();
+        main.register_relationship::<Likes>();
+        let mut other = World::new();
+        let outside = main.spawn(());
+        let e = main.spawn((Likes(outside),));
+        let fan = main.spawn((Likes(e),));
+        let child = main.spawn((Likes(e),));
+        main.add_child(e, child).unwrap();
+
+        let moved = main.transfer_entity(e, &mut other).unwrap();
+        // detached from the entities left behind, in both directions.
+        assert!(main.get::<&Likes>(fan).is_err());
+        assert!(main.get::<&LikedBy>(outside).is_err());
+        assert!(other.get::<&Likes>(moved).is_err());
+        let moved_child = other.get::<&Children>(moved).unwrap()[0];
+        assert_eq!(moved, other.get::<&Likes>(moved_child).unwrap().0);
+        assert_eq!(vec![moved_child], other.get::<&LikedBy>(moved).unwrap().0);
+    }
+
+    #[test]
+    fn merge_worlds() {
+        let mut main = World::new();
+        main.insert_resource(Level(1));
+        let _occupied = main.spawn(());
+        let mut chunk = World::new();
+        chunk.insert_resource(Level(2));
+        chunk.insert_resource(Seed(7));
+        let a = chunk.spawn(("a",));
+        let b = chunk.spawn((Target(a),));
+        chunk.add_child(a, b).unwrap();
+
+        let map = main.merge(chunk, ResourceMerge::Keep);
+        assert_eq!(2, map.len());
+        let (a, b) = (map.map(a), map.map(b));
+        assert_eq!("a", *main.get::<&&str>(a).unwrap());
+        assert_eq!([b], main.get::<&Children>(a).unwrap()[..]);
+        assert_eq!(Level(1), *main.get_resource::<Level>());
+        assert_eq!(Seed(7), *main.get_resource::<Seed>());
+
+        let mut chunk = World::new();
+        chunk.insert_resource(Level(3));
+        let _ = main.merge(chunk, ResourceMerge::Replace);
+        assert_eq!(Level(3), *main.get_resource::<Level>());
+        assert_eq!(Seed(7), *main.get_resource::<Seed>());
+    }
+
+    #[test]
+    fn merge_combines_registries() {
+        let mut main = World::new();
+        let _ = main.type_registry_mut().register_named::<Level>("Level");
+        let mut chunk = World::new();
+        let _ = chunk.type_registry_mut().register_named::<Level>("ChunkLevel");
+        let _ = chunk.type_registry_mut().register::<Target>().with_map_entities();
+
+        let _ = main.merge(chunk, ResourceMerge::Replace);
+        let registry = main.get_resource::<TypeRegistry>();
+        assert_eq!("Level", registry.get(TypeId::of::<Level>()).unwrap().name());
+        assert!(registry.get_by_name("ChunkLevel").is_none());
+        assert!(registry.get(TypeId::of::<Target>()).is_some());
+    }
+
+    #[test]
+    fn transfer_refuses() {
+        let mut main = World::new();
+        let mut other = World::new();
+        let resources = main.resource_entity();
+        assert_eq!(Err(TransferError::ResourceEntity), main.transfer_entity(resources, &mut other));
+        let observer = main.observe(|_: Trigger<Ping>| {});
+        let system = main.register_system(|| {});
+        assert_eq!(Err(TransferError::HoldsSystem), main.transfer_entity(observer, &mut other));
+        let parent = main.spawn(());
+        main.add_child(parent, system.entity()).unwrap();
+        assert_eq!(Err(TransferError::HoldsSystem), main.transfer_entity(parent, &mut other));
+        assert!(main.contains(parent) && main.contains(observer));
+        assert_eq!(1, other.len());
+    }
+
+    #[test]
+    fn merge_drops_systems() {
+        let mut main = World::new();
+        main.insert_resource(Level(0));
+        let mut chunk = World::new();
+        chunk.observe(|_: Trigger<Ping>, level: &mut Level| level.0 += 1);
+        let _ = chunk.register_system(|level: &mut Level| level.0 += 10);
+        let kept = chunk.spawn((7u32,));
+
+        let map = main.merge(chunk, ResourceMerge::Keep);
+        assert_eq!(1, map.len());
+        assert_eq!(7, *main.get::<&u32>(map.map(kept)).unwrap());
+        main.trigger(Ping);
+        assert_eq!(Level(0), *main.get_resource::<Level>());
+    }
+}
@@ -0,0 +1,290 @@
+//! Runtime information about component and resource types, looked up by [`TypeId`] or by name,
+//! for tools which only know types at runtime, like scenes, inspectors and scripting.
+//! Used to save and load worlds, with the `serde` feature.
+//!
+//! Types are registered in the world's [`TypeRegistry`] resource, through
+//! [`World::type_registry_mut`] or, for types deriving [`Registered`], [`World::register_type`].
+use core::{any::TypeId, fmt::Debug, marker::PhantomData};
+use std::borrow::Cow;
+
+use bevy_utils::{HashMap, TypeIdMap};
+use hecs::{Component, Entity, EntityBuilderClone, EntityRef, NoSuchEntity};
+
+use crate::{entitymap::{EntityMap, MapEntities}, resource::Resource, World};
+pub use bhecs_macros::Registered;
+
+/// Updates the entity references of one component of an entity, if it has one.
+pub(crate) type MapEntitiesFn = fn(&hecs::World, Entity, &EntityMap);
+/// Adds a clone of one component of an entity to the builder, if it has one.
+pub(crate) type CloneFn = fn(&EntityRef<'_>, &mut EntityBuilderClone);
+type DefaultFn = fn(&mut World, Entity) -> Result<(), NoSuchEntity>;
+type DebugFn = fn(&EntityRef<'_>) -> Option<String>;
+
+/// Everything known about one registered type.
+#[derive(Clone)]
+pub struct TypeRegistration {
+    name: Cow<'static, str>,
+    type_id: TypeId,
+    type_name: &'static str,
+    size: usize,
+    pub(crate) map_entities: Option<MapEntitiesFn>,
+    pub(crate) clone: Option<CloneFn>,
+    default: Option<DefaultFn>,
+    debug: Option<DebugFn>,
+    pub(crate) reflect: Option<crate::reflect::ReflectFn>,
+    #[cfg(feature = "serde")]
+    pub(crate) serde: Option<crate::serialize::SerdeFns>,
+}
+
+impl TypeRegistration {
+    /// The name the type is saved under.
+    #[must_use]
+    pub fn name(&self) -> &str { &self.name }
+    #[must_use]
+    pub fn type_id(&self) -> TypeId { self.type_id }
+    /// The rust type name, which unlike [`name`](Self::name) may change between builds.
+    #[must_use]
+    pub fn type_name(&self) -> &'static str { self.type_name }
+    #[must_use]
+    pub fn size(&self) -> usize { self.size }
+    #[must_use]
+    pub fn is_clone(&self) -> bool { self.clone.is_some() }
+    #[must_use]
+    pub fn is_default(&self) -> bool { self.default.is_some() }
+    #[must_use]
+    pub fn is_debug(&self) -> bool { self.debug.is_some() }
+    #[must_use]
+    pub fn is_reflect(&self) -> bool { self.reflect.is_some() }
+    #[cfg(feature = "serde")]
+    #[must_use]
+    pub fn is_serde(&self) -> bool { self.serde.is_some() }
+
+    /// Inserts the default value of the type into `entity`, running hooks and observers.
+    /// `None` if the type wasn't registered with [`RegisterType::with_default`].
+    pub fn insert_default(&self, world: &mut World, entity: Entity) -> Option<Result<(), NoSuchEntity>> {
+        Some((self.default?)(world, entity))
+    }
+    /// Formats the component of `entity` with [`Debug`]. `None` if the type wasn't registered
+    /// with [`RegisterType::with_debug`], or the entity doesn't have the component.
+    #[must_use]
+    pub fn debug_component(&self, entity: &EntityRef<'_>) -> Option<String> {
+        (self.debug?)(entity)
+    }
+}
+
+/// The registered types, a resource so systems and world methods can find it.
+#[derive(Default, Clone, Resource)]
+pub struct TypeRegistry {
+    types: TypeIdMap<TypeRegistration>,
+    names: HashMap<Cow<'static, str>, TypeId>,
+}
+
+impl TypeRegistry {
+    /// Registers `T` under its type name. Registering again returns the existing registration,
+    /// whatever its name.
+    pub fn register<T: 'static>(&mut self) -> RegisterType<'_, T> {
+        let name = match self.types.get(&TypeId::of::<T>()) {
+            Some(registration) => registration.name.clone(),
+            None => core::any::type_name::<T>().into(),
+        };
+        self.register_named::<T>(name)
+    }
+    /// Registers `T` under `name`, which should stay the same across builds, unlike type names.
+    /// Registering again under the same name returns the existing registration.
+    ///
+    /// # Panics
+    /// If another type already has `name`, or `T` is already registered under another name.
+    pub fn register_named<T: 'static>(&mut self, name: impl Into<Cow<'static, str>>) -> RegisterType<'_, T> {
+        let type_id = TypeId::of::<T>();
+        let name = name.into();
+        if let Some(registration) = self.types.get(&type_id) {
+            assert_eq!(registration.name, name, "{} is already registered as {}", registration.type_name, registration.name);
+        } else {
+            let owner = *self.names.entry(name.clone()).or_insert(type_id);
+            assert_eq!(type_id, owner, "type name {name} is already registered for another type");
+        }
+        let registration = self.types.entry(type_id).or_insert_with(|| TypeRegistration {
+            name,
+            type_id,
+            type_name: core::any::type_name::<T>(),
+            size: size_of::<T>(),
+            map_entities: None,
+            clone: None,
+            default: None,
+            debug: None,
+            reflect: None,
+            #[cfg(feature = "serde")]
+            serde: None,
+        });
+        RegisterType { registration, marker: PhantomData }
+    }
+    #[must_use]
+    pub fn get(&self, type_id: TypeId) -> Option<&TypeRegistration> {
+        self.types.get(&type_id)
+    }
+    #[must_use]
+    pub fn get_by_name(&self, name: &str) -> Option<&TypeRegistration> {
+        self.get(*self.names.get(name)?)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &TypeRegistration> {
+        self.types.values()
+    }
+    /// Adds the registrations of `other` for the types not registered here, unless their name is taken.
+    pub(crate) fn merge(&mut self, other: TypeRegistry) {
+        for (type_id, registration) in other.types {
+            if self.types.contains_key(&type_id) || self.names.contains_key(&registration.name) {
+                continue;
+            }
+            self.names.insert(registration.name.clone(), type_id);
+            self.types.insert(type_id, registration);
+        }
+    }
+    /// Registers `T` the way it registers itself.
+    pub fn add<T: Registered>(&mut self) -> &mut Self {
+        T::register(self);
+        self
+    }
+}
+
+/// Types which know how to register themselves, usually through the derive, which takes the
+/// registered name, the type's own name by default, and the methods of [`RegisterType`] to call:
+///
+/// ```
+/// # use bhecs::typeregistry::Registered;
+/// #[derive(Clone, Default, Debug, Registered)]
+/// #[registered(name = "Health", with_clone, with_default, with_debug)]
+/// struct Health(u32);
+///
+/// let mut world = bhecs::World::new();
+/// world.register_type::<Health>();
+/// assert!(world.type_registry_mut().get_by_name("Health").unwrap().is_clone());
+/// ```
+pub trait Registered: 'static {
+    fn register(registry: &mut TypeRegistry);
+}
+
+impl World {
+    /// The world's [`TypeRegistry`], inserted first if there is none.
+    pub fn type_registry_mut(&mut self) -> hecs::RefMut<'_, TypeRegistry> {
+        if self.hworld.get::<&TypeRegistry>(self.resource_entity()).is_err() {
+            self.insert_resource(TypeRegistry::default());
+        }
+        self.get_resource_mut()
+    }
+    /// Adds `T` to the world's [`TypeRegistry`], for plugins registering their types.
+    pub fn register_type<T: Registered>(&mut self) {
+        self.type_registry_mut().add::<T>();
+    }
+}
+
+/// Adds capabilities to the registration of `T`, see [`TypeRegistry::register`].
+pub struct RegisterType<'a, T> {
+    pub(crate) registration: &'a mut TypeRegistration,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> RegisterType<'_, T> {
+    /// Lets copies of `T` into another world have their entity references remapped.
+    #[must_use]
+    pub fn with_map_entities(self) -> Self
+    where
+        T: MapEntities,
+    {
+        self.registration.map_entities = Some(|world, entity, map| {
+            if let Ok(mut component) = world.get::<&mut T>(entity) {
+                component.map_entities(map);
+            }
+        });
+        self
+    }
+    /// Lets entities with `T` be cloned, and snapshotted.
+    #[must_use]
+    pub fn with_clone(self) -> Self
+    where
+        T: Clone,
+    {
+        self.registration.clone = Some(|entity, builder| {
+            if let Some(component) = entity.get::<&T>() {
+                builder.add(T::clone(&component));
+            }
+        });
+        self
+    }
+    /// Lets [`TypeRegistration::insert_default`] insert `T`.
+    #[must_use]
+    pub fn with_default(self) -> Self
+    where
+        T: Default,
+    {
+        self.registration.default = Some(|world, entity| world.insert_one(entity, T::default()));
+        self
+    }
+    /// Lets [`TypeRegistration::debug_component`] format `T`.
+    #[must_use]
+    pub fn with_debug(self) -> Self
+    where
+        T: Debug,
+    {
+        self.registration.debug = Some(|entity| entity.get::<&T>().map(|component| format!("{:?}", &*component)));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default, Debug, PartialEq, Registered)]
+    #[registered(with_clone, with_default, with_debug)]
+    struct Health(u32);
+
+    #[test]
+    fn lookup() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register_named::<Health>("Health");
+        let by_name = registry.get_by_name("Health").unwrap();
+        assert_eq!(TypeId::of::<Health>(), by_name.type_id());
+        assert_eq!("Health", registry.get(TypeId::of::<Health>()).unwrap().name());
+        // registering again keeps the first registration.
+        let _ = registry.register_named::<Health>("Health");
+        assert_eq!(1, registry.iter().count());
+    }
+
+    #[test]
+    fn capabilities() {
+        let mut w = World::new();
+        w.register_type::<Health>();
+        let _ = w.type_registry_mut().register::<u8>();
+        let registry = TypeRegistry::clone(&w.get_resource());
+        let health = registry.get_by_name("Health").unwrap();
+        assert_eq!(size_of::<u32>(), health.size());
+        assert!(health.is_clone() && health.is_default() && health.is_debug());
+        let byte = registry.get(TypeId::of::<u8>()).unwrap();
+        assert_eq!("u8", byte.name());
+        assert!(!byte.is_clone());
+
+        let e = w.spawn(());
+        assert!(byte.insert_default(&mut w, e).is_none());
+        health.insert_default(&mut w, e).unwrap().unwrap();
+        assert_eq!(Health(0), *w.get::<&Health>(e).unwrap());
+        assert_eq!(Some("Health(0)".into()), health.debug_component(&w.entity(e).unwrap()));
+    }
+
+    #[test]
+    #[should_panic(expected = "already registered for another type")]
+    fn name_clash() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register_named::<Health>("Health");
+        let _ = registry.register_named::<u32>("Health");
+    }
+
+    #[test]
+    #[should_panic(expected = "is already registered as Health")]
+    fn second_name() {
+        let mut registry = TypeRegistry::default();
+        let _ = registry.register_named::<Health>("Health");
+        // register keeps the name, only another explicit name is refused.
+        let _ = registry.register::<Health>();
+        let _ = registry.register_named::<Health>("Hp");
+    }
+}
@@ -1,14 +1,35 @@
+pub mod deferredworld;
+mod structural;
+
+use bevy_utils::TypeIdMap;
 use derive_more::derive::{Deref, DerefMut};
 use hecs::Entity;
 
-use crate::{resource::{Resource, ResourceComponent}, ChangeTick};
+use crate::{changetick::CHECK_TICK_THRESHOLD, hooks::ComponentHooks, observer::{LifecycleTrigger, LifecycleTriggers}, relationship::RelationshipFns, removal::RemovedLog, system::commands::CommandQueue, resource::{Resource, ResourceComponent}, ChangeTick};
 
 #[derive(Deref, DerefMut)]
 pub struct World {
     #[deref] #[deref_mut]
     pub(crate) hworld: hecs::World,
-    change_tick: ChangeTick,
+    pub(crate) change_tick: ChangeTick,
+    pub(crate) last_check_tick: ChangeTick,
     resource_entity: Entity,
+    /// Clamps the ticks stored in the world, keyed by the type storing them.
+    /// So its keys are the components holding systems, like those of observers.
+    pub(crate) tick_checks: TypeIdMap<fn(&mut World, ChangeTick)>,
+    /// Removals recorded for [`RemovedComponents`](crate::removal::RemovedComponents), by component type.
+    pub(crate) removed: TypeIdMap<RemovedLog>,
+    pub(crate) hooks: TypeIdMap<ComponentHooks>,
+    /// Registered [`Relationship`](crate::relationship::Relationship)s, by their component type.
+    pub(crate) relationships: TypeIdMap<RelationshipFns>,
+    /// Commands queued by hooks, applied when the operation which ran them is done.
+    pub(crate) command_queue: CommandQueue,
+    /// Observed lifecycle events, by component type.
+    pub(crate) lifecycle: TypeIdMap<LifecycleTriggers>,
+    /// Observers which ran before a removal, and apply their deferred buffers after it.
+    pub(crate) pending_observers: Vec<(Entity, LifecycleTrigger)>,
+    /// Holds the components of an entity given out by [`take`](World::take) while its removal's commands run.
+    taken: hecs::World,
 }
 impl Default for World {
     fn default() -> Self {
@@ -17,18 +38,56 @@ impl Default for World {
 }
 
 impl World {
-    pub(crate) fn increment_change_tick(&mut self) -> u32 {
-        self.change_tick += 1;
+    pub(crate) fn increment_change_tick(&mut self) -> ChangeTick {
+        self.change_tick = self.change_tick.wrapping_add(1);
         return self.change_tick;
     }
+    /// Clamps ticks stored in the world, like those of registered systems and observers, so they stay
+    /// comparable after the change tick wraps around. Only does work once every [`CHECK_TICK_THRESHOLD`] ticks,
+    /// so it is cheap enough to be called before every run of a system or observer, which it is.
+    /// Systems owned outside the world clamp their own ticks in [`System::run`](crate::system::System::run),
+    /// others need [`System::check_change_tick`](crate::system::System::check_change_tick) called by hand.
+    pub fn check_change_ticks(&mut self) {
+        let change_tick = self.change_tick;
+        if change_tick.wrapping_sub(self.last_check_tick) < CHECK_TICK_THRESHOLD {
+            return;
+        }
+        self.clamp_ticks(change_tick);
+        self.last_check_tick = change_tick;
+    }
+    /// Clamps the ticks stored in the world to be at most [`MAX_CHANGE_AGE`](crate::changetick::MAX_CHANGE_AGE)
+    /// older than `change_tick`, which also brings back those ahead of it.
+    pub(crate) fn clamp_ticks(&mut self, change_tick: ChangeTick) {
+        let checks: Vec<_> = self.tick_checks.values().copied().collect();
+        for check in checks {
+            check(self, change_tick);
+        }
+    }
+    pub fn change_tick(&self) -> ChangeTick { self.change_tick }
+    /// True for observers and registered systems, whose state belongs to this world.
+    pub(crate) fn holds_system(&self, entity: Entity) -> bool {
+        self.hworld.entity(entity)
+            .is_ok_and(|entity| entity.component_types().any(|type_id| self.tick_checks.contains_key(&type_id)))
+    }
     #[must_use]
     pub fn new() -> Self {
         let mut hworld = hecs::World::new();
         let resource_entity = hworld.spawn((ResourceComponent,));
-        return Self {
+        let mut world = Self {
             hworld, resource_entity, 
             change_tick: 1,
+            last_check_tick: 0,
+            tick_checks: TypeIdMap::default(),
+            removed: TypeIdMap::default(),
+            hooks: TypeIdMap::default(),
+            relationships: TypeIdMap::default(),
+            command_queue: CommandQueue::default(),
+            lifecycle: TypeIdMap::default(),
+            pending_observers: Vec::new(),
+            taken: hecs::World::new(),
         };
+        world.register_relationship::<crate::hierarchy::Parent>();
+        return world;
     }
     pub fn resource_entity(&self) -> Entity { self.resource_entity }
 
@@ -38,4 +97,7 @@ impl World {
     pub fn get_resource<R: Resource>(&mut self) -> hecs::Ref<'_, R> {
         self.hworld.get::<&R>(self.resource_entity).unwrap()
     }
+    pub fn get_resource_mut<R: Resource>(&mut self) -> hecs::RefMut<'_, R> {
+        self.hworld.get::<&mut R>(self.resource_entity).unwrap()
+    }
 }
@@ -0,0 +1,37 @@
+use core::ops::Deref;
+
+use crate::{resource::Resource, system::commands::Commands, World};
+
+/// A world which can't be changed structurally right now, e.g. while a component hook runs.
+///
+/// Components and resources can still be read and written, and structural changes can be
+/// queued with [`commands`](Self::commands). They are applied once the current world operation is done.
+pub struct DeferredWorld<'w> {
+    world: &'w mut World,
+}
+
+impl<'w> DeferredWorld<'w> {
+    pub(crate) fn new(world: &'w mut World) -> Self {
+        Self { world }
+    }
+    /// Commands applied right after the world operation which ran the hook.
+    pub fn commands(&mut self) -> Commands<'_, '_> {
+        Commands::new(&mut self.world.command_queue, &self.world.hworld)
+    }
+    #[must_use]
+    pub fn resource<R: Resource>(&self) -> hecs::Ref<'_, R> {
+        self.world.hworld.get::<&R>(self.world.resource_entity()).unwrap()
+    }
+    #[must_use]
+    pub fn resource_mut<R: Resource>(&self) -> hecs::RefMut<'_, R> {
+        self.world.hworld.get::<&mut R>(self.world.resource_entity()).unwrap()
+    }
+}
+
+impl Deref for DeferredWorld<'_> {
+    type Target = World;
+
+    fn deref(&self) -> &World {
+        self.world
+    }
+}
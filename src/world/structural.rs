@@ -0,0 +1,193 @@
+//! Structural changes which run component hooks and observers, and record removals.
+//! These shadow the methods of the same name on `hecs::World`.
+use core::any::TypeId;
+
+use hecs::{Bundle, Component, ComponentError, DynamicBundle, Entity, NoSuchEntity, TakenEntity};
+
+use crate::{hooks::{ComponentHook, ComponentHooks}, resource::ResourceComponent, World};
+
+fn on_add(hooks: &ComponentHooks) -> Option<ComponentHook> { hooks.add }
+fn on_insert(hooks: &ComponentHooks) -> Option<ComponentHook> { hooks.insert }
+fn on_replace(hooks: &ComponentHooks) -> Option<ComponentHook> { hooks.replace }
+fn on_remove(hooks: &ComponentHooks) -> Option<ComponentHook> { hooks.remove }
+
+impl World {
+    /// True if some hook or observer cares about changes of components of type `ty`.
+    fn is_hooked(&self, ty: TypeId) -> bool {
+        self.hooks.contains_key(&ty) || self.lifecycle.contains_key(&ty)
+    }
+    /// The hooked ones of `types`.
+    fn hooked(&self, types: &[TypeId]) -> Vec<TypeId> {
+        types.iter().copied().filter(|&ty| self.is_hooked(ty)).collect()
+    }
+    /// The hooked types of `entity`'s components.
+    fn hooked_types(&self, entity: Entity) -> Vec<TypeId> {
+        self.hworld.entity(entity)
+            .map(|e| e.component_types().filter(|&ty| self.is_hooked(ty)).collect())
+            .unwrap_or_default()
+    }
+    /// True if `entity` has components of all `types`.
+    fn has_types(&self, entity: Entity, types: &[TypeId]) -> bool {
+        self.hworld.entity(entity)
+            .is_ok_and(|e| types.iter().all(|&ty| e.component_types().any(|has| has == ty)))
+    }
+    fn before_removal(&mut self, entity: Entity, replaced: &[TypeId], removed: &[TypeId]) {
+        self.run_hooks(entity, replaced, on_replace);
+        self.run_hooks(entity, removed, on_replace);
+        self.run_hooks(entity, removed, on_remove);
+        self.run_lifecycle(entity, removed, |triggers| triggers.remove);
+    }
+    fn after_insertion(&mut self, entity: Entity, added: &[TypeId], inserted: &[TypeId]) {
+        self.run_hooks(entity, added, on_add);
+        self.run_hooks(entity, inserted, on_insert);
+        self.run_lifecycle(entity, added, |triggers| triggers.add);
+        self.flush_commands();
+    }
+
+    pub fn spawn(&mut self, components: impl DynamicBundle) -> Entity {
+        let entity = self.hworld.spawn(components);
+        let types = self.hooked_types(entity);
+        self.after_insertion(entity, &types, &types);
+        entity
+    }
+    /// Spawns at a specific handle, despawning whatever lived there first.
+    pub fn spawn_at(&mut self, handle: Entity, components: impl DynamicBundle) {
+        if self.hworld.contains(handle) {
+            let _ = self.despawn(handle);
+        }
+        self.hworld.spawn_at(handle, components);
+        let types = self.hooked_types(handle);
+        self.after_insertion(handle, &types, &types);
+    }
+    /// Like `hecs::World::spawn_batch`, but every bundle is spawned right away,
+    /// so hooks and observers run before this returns. Yields the new entities.
+    pub fn spawn_batch<I>(&mut self, iter: I) -> std::vec::IntoIter<Entity>
+    where
+        I: IntoIterator,
+        I::Item: Bundle + 'static,
+    {
+        let hooked = I::Item::with_static_ids(|ids| ids.iter().any(|&ty| self.is_hooked(ty)));
+        let entities: Vec<Entity> = if !hooked {
+            self.hworld.spawn_batch(iter).collect()
+        } else {
+            iter.into_iter().map(|components| self.spawn(components)).collect()
+        };
+        entities.into_iter()
+    }
+    pub fn insert(&mut self, entity: Entity, components: impl DynamicBundle) -> Result<(), NoSuchEntity> {
+        if !self.hworld.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        let before = self.hooked_types(entity);
+        let inserted = components.with_ids(|ids| self.hooked(ids));
+        let (replaced, added): (Vec<TypeId>, Vec<TypeId>) = inserted.iter().partition(|ty| before.contains(ty));
+        self.before_removal(entity, &replaced, &[]);
+        self.hworld.insert(entity, components)?;
+        self.after_insertion(entity, &added, &inserted);
+        Ok(())
+    }
+    pub fn insert_one(&mut self, entity: Entity, component: impl Component) -> Result<(), NoSuchEntity> {
+        self.insert(entity, (component,))
+    }
+
+    pub fn despawn(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        if !self.hworld.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        let types = self.hooked_types(entity);
+        self.before_removal(entity, &[], &types);
+        let tracked = self.tracked_types(entity);
+        self.hworld.despawn(entity)?;
+        self.record_removed(entity, &tracked);
+        self.flush_commands();
+        Ok(())
+    }
+    /// Despawns every entity but the resource entity, so resources are kept.
+    pub fn clear(&mut self) {
+        let resource_entity = self.resource_entity();
+        // entities of archetypes with hooked or tracked types are despawned one by one.
+        let one_by_one = self.hworld.archetypes()
+            .filter(|archetype| !archetype.is_empty() && !archetype.has::<ResourceComponent>())
+            .flat_map(hecs::Archetype::component_types)
+            .any(|ty| self.is_hooked(ty) || self.removed.contains_key(&ty));
+        if one_by_one {
+            let entities: Vec<Entity> = self.hworld.iter()
+                .map(|e| e.entity())
+                .filter(|&entity| entity != resource_entity)
+                .collect();
+            for &entity in &entities {
+                let types = self.hooked_types(entity);
+                self.before_removal(entity, &[], &types);
+            }
+            for entity in entities {
+                let tracked = self.tracked_types(entity);
+                self.hworld.despawn(entity).expect("entity was just listed");
+                self.record_removed(entity, &tracked);
+            }
+        } else {
+            // hecs can only clear everything, so the resources wait in a staging world meanwhile.
+            let mut staging = hecs::World::new();
+            let resources = staging.spawn(self.hworld.take(resource_entity).expect("resource entity exists"));
+            self.hworld.clear();
+            self.hworld.spawn_at(resource_entity, staging.take(resources).expect("just spawned"));
+        }
+        self.flush_commands();
+    }
+    pub fn remove<T: Bundle + 'static>(&mut self, entity: Entity) -> Result<T, ComponentError> {
+        let removed = T::with_static_ids(|ids| self.hooked(ids));
+        // hooks only run if the removal will succeed.
+        if !removed.is_empty() && T::with_static_ids(|ids| self.has_types(entity, ids)) {
+            self.before_removal(entity, &[], &removed);
+        }
+        let tracked = self.tracked_types(entity);
+        let components = self.hworld.remove::<T>(entity)?;
+        self.record_removed(entity, &tracked);
+        self.flush_commands();
+        Ok(components)
+    }
+    pub fn remove_one<T: Component>(&mut self, entity: Entity) -> Result<T, ComponentError> {
+        self.remove::<(T,)>(entity).map(|(x,)| x)
+    }
+    /// Removes `S` and inserts `components` in one move. Types in both count as replaced.
+    pub fn exchange<S: Bundle + 'static, T: DynamicBundle>(&mut self, entity: Entity, components: T) -> Result<S, ComponentError> {
+        let before = self.hooked_types(entity);
+        let removed = S::with_static_ids(|ids| self.hooked(ids));
+        let inserted = components.with_ids(|ids| self.hooked(ids));
+        // hooks only run if the exchange will succeed.
+        if !S::with_static_ids(|ids| self.has_types(entity, ids)) {
+            return self.hworld.exchange::<S, T>(entity, components);
+        }
+        let (replaced, added): (Vec<TypeId>, Vec<TypeId>) = inserted.iter()
+            .partition(|ty| before.contains(ty) || removed.contains(ty));
+        let only_removed: Vec<TypeId> = removed.iter().filter(|ty| !inserted.contains(ty)).copied().collect();
+        self.before_removal(entity, &replaced, &only_removed);
+        let tracked = self.tracked_types(entity);
+        let old = self.hworld.exchange::<S, T>(entity, components)?;
+        self.record_removed(entity, &tracked);
+        self.after_insertion(entity, &added, &inserted);
+        Ok(old)
+    }
+    pub fn exchange_one<S: Component, T: Component>(&mut self, entity: Entity, component: T) -> Result<S, ComponentError> {
+        self.exchange::<(S,), (T,)>(entity, (component,)).map(|(x,)| x)
+    }
+    /// Despawns `entity`, handing its components back. They count as removed.
+    /// Like for `despawn`, commands queued by hooks and observers run after the entity is gone.
+    pub fn take(&mut self, entity: Entity) -> Result<TakenEntity<'_>, NoSuchEntity> {
+        if !self.hworld.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        let types = self.hooked_types(entity);
+        self.before_removal(entity, &[], &types);
+        let tracked = self.tracked_types(entity);
+        for ty in tracked {
+            self.removed.get_mut(&ty).expect("tracked type").push(entity);
+        }
+        if self.command_queue.is_empty() && self.pending_observers.is_empty() {
+            return self.hworld.take(entity);
+        }
+        // the commands need the world, so the components wait in the staging world meanwhile.
+        let staged = self.taken.spawn(self.hworld.take(entity)?);
+        self.flush_commands();
+        Ok(self.taken.take(staged).expect("just spawned"))
+    }
+}